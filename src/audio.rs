@@ -1,8 +1,19 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Args, Subcommand};
-use std::{path::PathBuf, process::Command};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 
-use crate::utils::{get_file_arg, run_ffmpeg_command, FileType};
+use crate::image::{create_video_from_still, AudioTrack, StillSource};
+use crate::probe::probe;
+use crate::utils::{
+    audio_codec_by_ext, ext_by_filename, file_type_from_extension, get_file_arg, parse_dimensions,
+    run_ffmpeg_command, run_ffmpeg_command_with_log, FileType,
+};
+use crate::video::Anchor;
 
 #[derive(Subcommand)]
 pub enum AudioCommand {
@@ -11,6 +22,15 @@ pub enum AudioCommand {
 
     /// Change audio volume
     Volume(VolumeArgs),
+
+    /// Fade audio in and/or out
+    Fade(FadeAudioArgs),
+
+    /// Render an animated waveform or spectrum over an image (audiogram)
+    Visualize(VisualizeArgs),
+
+    /// Normalize loudness (EBU R128) or peak level, for audio or video files
+    Normalize(NormalizeArgs),
 }
 
 impl AudioCommand {
@@ -18,6 +38,9 @@ impl AudioCommand {
         match self {
             Self::Combine(cmd) => cmd.execute(),
             Self::Volume(cmd) => cmd.execute(),
+            Self::Fade(cmd) => cmd.execute(),
+            Self::Visualize(cmd) => cmd.execute(),
+            Self::Normalize(cmd) => cmd.execute(),
         }
     }
 }
@@ -76,21 +99,104 @@ pub struct VolumeArgs {
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
-    /// Volume multiplier (e.g., 0.5 for half volume, 2.0 for double)
+    /// Volume as a multiplier (0.5, 2.0) or in decibels (+3dB, -6dB)
+    #[arg(short, long, allow_hyphen_values = true)]
+    pub volume: Gain,
+
+    /// Extra gain for one channel as CHANNEL=GAIN (e.g. 1=-3dB), repeatable
     #[arg(short, long)]
-    pub volume: f32,
+    pub channel_gain: Vec<ChannelGain>,
+
+    /// Limit peaks to this level in dBFS with alimiter (defaults to -1 when given without a value)
+    #[arg(
+        short,
+        long,
+        num_args = 0..=1,
+        default_missing_value = "-1",
+        allow_negative_numbers = true
+    )]
+    pub limit: Option<f64>,
 }
 
 impl VolumeArgs {
+    /// Filter chain: overall gain, per-channel gains, then the limiter.
+    /// `layout` is the input channel layout, kept through the gain stage.
+    pub fn filter(&self, channels: u32, layout: Option<&str>) -> Result<String> {
+        let mut filters = vec![format!("volume={}", self.volume)];
+
+        if !self.channel_gain.is_empty() {
+            let mut gains = vec![1.0; channels as usize];
+            for channel_gain in &self.channel_gain {
+                let gain = gains.get_mut(channel_gain.channel).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Channel {} does not exist, the input has {} channel(s)",
+                        channel_gain.channel,
+                        channels
+                    )
+                })?;
+                *gain *= channel_gain.gain.multiplier();
+            }
+            let mapping = gains
+                .iter()
+                .enumerate()
+                .map(|(i, gain)| format!("c{}={}*c{}", i, gain, i))
+                .collect::<Vec<_>>()
+                .join("|");
+            let layout = layout
+                .map(String::from)
+                .unwrap_or_else(|| format!("{}c", channels));
+            filters.push(format!("pan={}|{}", layout, mapping));
+        }
+
+        if let Some(limit) = self.limit {
+            if !(-24.0..=0.0).contains(&limit) {
+                anyhow::bail!("Limit must be between -24 and 0 dBFS");
+            }
+            let linear = 10f64.powf(limit / 20.0);
+            filters.push(format!("alimiter=limit={:.4}:level=0", linear));
+        }
+
+        Ok(filters.join(","))
+    }
+
+    /// Largest gain any channel receives, in dB.
+    pub fn max_gain_db(&self) -> f64 {
+        let channel_boost = self
+            .channel_gain
+            .iter()
+            .map(|c| c.gain.decibels())
+            .fold(0.0, f64::max);
+        self.volume.decibels() + channel_boost
+    }
+
     pub fn execute(&self) -> Result<()> {
         // Get output or prompt for one
         let output = get_file_arg(FileType::Audio, &self.output)?;
 
+        let info = probe(&self.input)?;
+        let stream = info
+            .audio_stream()
+            .ok_or_else(|| anyhow::anyhow!("No audio track found"))?;
+        let filter = self.filter(
+            stream.channels.unwrap_or(2),
+            stream.channel_layout.as_deref(),
+        )?;
+
+        if self.limit.is_none() && self.max_gain_db() > 0.0 {
+            let peak = measure_peak(&self.input)? + self.max_gain_db();
+            if peak > 0.0 {
+                eprintln!(
+                    "Warning: peak would reach {:+.1} dBFS and clip; use --limit to prevent it",
+                    peak
+                );
+            }
+        }
+
         let args = [
             "-i",
             self.input.to_str().unwrap(),
             "-filter:a",
-            &format!("volume={}", self.volume),
+            &filter,
             "-y",
             output.to_str().unwrap(),
         ];
@@ -99,9 +205,157 @@ impl VolumeArgs {
     }
 }
 
-pub fn get_audio_duration(path: &PathBuf) -> Result<f64> {
+/// A gain given as a multiplier (`2.0`) or in decibels (`+3dB`, `-6dB`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gain {
+    Multiplier(f64),
+    Decibels(f64),
+}
+
+impl Gain {
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            Self::Multiplier(m) => *m,
+            Self::Decibels(db) => 10f64.powf(db / 20.0),
+        }
+    }
+
+    pub fn decibels(&self) -> f64 {
+        match self {
+            Self::Multiplier(m) => 20.0 * m.log10(),
+            Self::Decibels(db) => *db,
+        }
+    }
+}
+
+impl FromStr for Gain {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let lower = s.to_lowercase();
+        if let Some(db) = lower.strip_suffix("db") {
+            let db: f64 = db
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid gain: {}", s))?;
+            return Ok(Self::Decibels(db));
+        }
+        match s.parse::<f64>() {
+            Ok(m) if m >= 0.0 => Ok(Self::Multiplier(m)),
+            _ => anyhow::bail!(
+                "Invalid gain: {}. Use a multiplier (2.0) or decibels (+3dB)",
+                s
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Gain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Multiplier(m) => write!(f, "{}", m),
+            Self::Decibels(db) => write!(f, "{}dB", db),
+        }
+    }
+}
+
+/// A gain applied to a single channel, given as `CHANNEL=GAIN` (`1=-3dB`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelGain {
+    pub channel: usize,
+    pub gain: Gain,
+}
+
+impl FromStr for ChannelGain {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (channel, gain) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel gain: {}. Use CHANNEL=GAIN", s))?;
+        let channel = channel
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid channel index: {}", channel))?;
+        Ok(Self {
+            channel,
+            gain: gain.parse()?,
+        })
+    }
+}
+
+/// Build an `afade` chain. The fade-out starts `fade_out` seconds before
+/// `duration`.
+pub fn afade_filter(
+    fade_in: Option<f64>,
+    fade_out: Option<f64>,
+    curve: &str,
+    duration: f64,
+) -> Option<String> {
+    let mut filters = Vec::new();
+    if let Some(fade_in) = fade_in {
+        filters.push(format!("afade=t=in:st=0:d={}:curve={}", fade_in, curve));
+    }
+    if let Some(fade_out) = fade_out {
+        filters.push(format!(
+            "afade=t=out:st={}:d={}:curve={}",
+            (duration - fade_out).max(0.0),
+            fade_out,
+            curve
+        ));
+    }
+    (!filters.is_empty()).then(|| filters.join(","))
+}
+
+#[derive(Args)]
+pub struct FadeAudioArgs {
+    /// Input audio file
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output file path
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Fade in duration in seconds
+    #[arg(long)]
+    pub fade_in: Option<f64>,
+
+    /// Fade out duration in seconds
+    #[arg(long)]
+    pub fade_out: Option<f64>,
+
+    /// Fade curve: tri, qsin, hsin, esin, log, ipar, qua, cub, squ, cbr, par, exp
+    #[arg(long, default_value = "tri")]
+    pub curve: String,
+}
+
+impl FadeAudioArgs {
+    pub fn execute(&self) -> Result<()> {
+        let duration = get_audio_duration(&self.input)?;
+        let filter = afade_filter(self.fade_in, self.fade_out, &self.curve, duration)
+            .ok_or_else(|| anyhow::anyhow!("Specify --fade-in and/or --fade-out"))?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Audio, &self.output)?;
+
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-filter:a",
+            &filter,
+            "-y",
+            output.to_str().unwrap(),
+        ];
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+pub fn get_audio_duration(path: &Path) -> Result<f64> {
     let output = Command::new("ffprobe")
-        .args(&[
+        .args([
             "-v",
             "error",
             "-show_entries",
@@ -115,3 +369,337 @@ pub fn get_audio_duration(path: &PathBuf) -> Result<f64> {
     let duration_str = String::from_utf8(output.stdout)?;
     duration_str.trim().parse::<f64>().map_err(Into::into)
 }
+
+#[derive(Args)]
+pub struct VisualizeArgs {
+    /// Input audio file
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output video file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Background image (scaled and cropped to fill the video)
+    #[arg(short, long)]
+    pub background: Option<PathBuf>,
+
+    /// Background colour when no image is given
+    #[arg(long, default_value = "black")]
+    pub background_color: String,
+
+    /// Visualization style: waves, spectrum, or bars
+    #[arg(short, long, default_value = "waves")]
+    pub style: String,
+
+    /// Waveform/bar colour (the spectrum uses an intensity palette)
+    #[arg(short, long, default_value = "white")]
+    pub color: String,
+
+    /// Video size WxH
+    #[arg(long, default_value = "1280x720")]
+    pub size: String,
+
+    /// Visualization size WxH
+    #[arg(long, default_value = "1280x240")]
+    pub visual_size: String,
+
+    /// Visualization position (top-left, top, ..., bottom-right)
+    #[arg(short, long, default_value = "bottom")]
+    pub position: Anchor,
+
+    /// Distance from the edges in pixels
+    #[arg(long, default_value_t = 40)]
+    pub margin: u32,
+
+    /// Frame rate
+    #[arg(long, default_value_t = 30)]
+    pub fps: u32,
+}
+
+impl VisualizeArgs {
+    /// Filter graph over the looped background `[0:v]` and the audio `[1:a]`,
+    /// writing `[v]`.
+    pub fn filter_graph(&self) -> Result<String> {
+        let (width, height) = parse_dimensions(&self.size)?;
+        let (visual_w, visual_h) = parse_dimensions(&self.visual_size)?;
+        if visual_w > width || visual_h > height {
+            anyhow::bail!("The visualization must fit inside the video");
+        }
+
+        let visual = match self.style.as_str() {
+            "waves" => format!(
+                "showwaves=s={}x{}:mode=cline:colors={}:rate={}",
+                visual_w, visual_h, self.color, self.fps
+            ),
+            "spectrum" => format!(
+                "showspectrum=s={}x{}:mode=combined:color=intensity:slide=scroll:fps={}",
+                visual_w, visual_h, self.fps
+            ),
+            "bars" => format!(
+                "showfreqs=s={}x{}:mode=bar:ascale=log:fscale=log:colors={}:rate={}",
+                visual_w, visual_h, self.color, self.fps
+            ),
+            _ => anyhow::bail!("Invalid visualization style. Use waves, spectrum, or bars"),
+        };
+        let (x, y) = self
+            .position
+            .expressions(("W", "H"), ("w", "h"), self.margin);
+
+        Ok(format!(
+            "[1:a]{visual},format=rgba[vis];[0:v]scale={width}:{height}:force_original_aspect_ratio=increase,crop={width}:{height},setsar=1,fps={fps}[bg];[bg][vis]overlay={x}:{y}:shortest=1,format=yuv420p[v]",
+            fps = self.fps
+        ))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let graph = self.filter_graph()?;
+        let duration = probe(&self.input)?
+            .duration()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine audio duration"))?;
+
+        let (width, height) = parse_dimensions(&self.size)?;
+        let image = self.background.as_ref().map(image::open).transpose()?;
+        let source = match &image {
+            Some(img) => StillSource::Image(img),
+            None => StillSource::Color {
+                color: &self.background_color,
+                width,
+                height,
+            },
+        };
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        let track = AudioTrack {
+            audio: &self.input,
+            filter_complex: &graph,
+        };
+        create_video_from_still(source, &output, duration, Some(track))
+    }
+}
+
+/// Measurements printed by `loudnorm=print_format=json`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LoudnormStats {
+    pub input_i: String,
+    pub input_tp: String,
+    pub input_lra: String,
+    pub input_thresh: String,
+    pub output_i: String,
+    pub output_tp: String,
+    pub target_offset: String,
+}
+
+/// Parse the JSON block `loudnorm` prints at the end of its log.
+pub fn parse_loudnorm_stats(log: &str) -> Result<LoudnormStats> {
+    let start = log
+        .rfind('{')
+        .ok_or_else(|| anyhow::anyhow!("loudnorm did not report any measurements"))?;
+    let end = log[start..]
+        .find('}')
+        .ok_or_else(|| anyhow::anyhow!("loudnorm did not report any measurements"))?;
+    serde_json::from_str(&log[start..=start + end]).context("Failed to parse loudnorm output")
+}
+
+/// Maximum sample level in dBFS reported by `volumedetect`.
+pub fn parse_max_volume(log: &str) -> Option<f64> {
+    log.lines()
+        .filter_map(|line| line.split("max_volume:").nth(1))
+        .filter_map(|value| value.trim().trim_end_matches("dB").trim().parse().ok())
+        .next_back()
+}
+
+/// Measure the peak level of the first audio track.
+pub fn measure_peak(input: &Path) -> Result<f64> {
+    let log = run_ffmpeg_command_with_log(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-map",
+        "0:a:0",
+        "-af",
+        "volumedetect",
+        "-f",
+        "null",
+        "-",
+    ])?;
+    parse_max_volume(&log).ok_or_else(|| anyhow::anyhow!("volumedetect did not report a peak"))
+}
+
+/// Output options for the normalizing pass. The first audio track, the one
+/// that was measured, is filtered and encoded with `codec`; with
+/// `keep_streams` every other stream is copied unchanged.
+pub fn normalize_output_args(
+    filter: &str,
+    sample_rate: &str,
+    codec: &str,
+    keep_streams: bool,
+) -> Vec<String> {
+    // loudnorm works at 192 kHz internally, hence the explicit source rate
+    let args = if keep_streams {
+        [
+            "-map",
+            "0",
+            "-c",
+            "copy",
+            "-filter:a:0",
+            filter,
+            "-c:a:0",
+            codec,
+            "-ar:a:0",
+            sample_rate,
+        ]
+        .to_vec()
+    } else {
+        [
+            "-map",
+            "0:a:0",
+            "-af",
+            filter,
+            "-c:a",
+            codec,
+            "-ar",
+            sample_rate,
+        ]
+        .to_vec()
+    };
+    args.into_iter().map(String::from).collect()
+}
+
+#[derive(Args)]
+pub struct NormalizeArgs {
+    /// Input audio or video file
+    #[arg(short, long)]
+    pub input: PathBuf,
+
+    /// Output file path
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Integrated loudness target in LUFS (-16 for podcasts/streaming, -23 for broadcast)
+    #[arg(short, long, default_value_t = -16.0, allow_hyphen_values = true)]
+    pub target: f64,
+
+    /// Maximum true peak in dBTP
+    #[arg(long, default_value_t = -1.5, allow_hyphen_values = true)]
+    pub true_peak: f64,
+
+    /// Loudness range target in LU
+    #[arg(long, default_value_t = 11.0)]
+    pub lra: f64,
+
+    /// Peak-normalize to this level in dBFS instead (e.g. -1)
+    #[arg(long, allow_hyphen_values = true)]
+    pub peak: Option<f64>,
+}
+
+impl NormalizeArgs {
+    /// `loudnorm` filter; with measurements from a first pass it applies a
+    /// linear gain instead of dynamic normalization.
+    pub fn loudnorm_filter(&self, measured: Option<&LoudnormStats>) -> Result<String> {
+        if !(-70.0..=-5.0).contains(&self.target) {
+            anyhow::bail!("Target must be between -70 and -5 LUFS");
+        }
+        if !(-9.0..=0.0).contains(&self.true_peak) {
+            anyhow::bail!("True peak must be between -9 and 0 dBTP");
+        }
+        if !(1.0..=50.0).contains(&self.lra) {
+            anyhow::bail!("LRA must be between 1 and 50 LU");
+        }
+
+        let mut filter = format!(
+            "loudnorm=I={}:TP={}:LRA={}",
+            self.target, self.true_peak, self.lra
+        );
+        if let Some(m) = measured {
+            filter.push_str(&format!(
+                ":measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+                m.input_i, m.input_tp, m.input_lra, m.input_thresh, m.target_offset
+            ));
+        }
+        filter.push_str(":print_format=json");
+        Ok(filter)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let ext = ext_by_filename(self.input.to_str().unwrap()).unwrap_or_default();
+        let is_video = matches!(
+            file_type_from_extension(&ext.to_lowercase()),
+            Ok(FileType::Video)
+        );
+        let info = probe(&self.input)?;
+        let sample_rate = info
+            .audio_stream()
+            .ok_or_else(|| anyhow::anyhow!("No audio track found"))?
+            .sample_rate
+            .clone()
+            .unwrap_or_else(|| "48000".to_string());
+
+        // Get output or prompt for one
+        let file_type = if is_video {
+            FileType::Video
+        } else {
+            FileType::Audio
+        };
+        let output = get_file_arg(file_type, &self.output)?;
+
+        let filter = match self.peak {
+            Some(peak) => {
+                let before = measure_peak(&self.input)?;
+                println!("Measured peak: {:.1} dBFS", before);
+                format!("volume={:.2}dB", peak - before)
+            }
+            None => {
+                let log = run_ffmpeg_command_with_log(&[
+                    "-i",
+                    self.input.to_str().unwrap(),
+                    "-map",
+                    "0:a:0",
+                    "-af",
+                    &self.loudnorm_filter(None)?,
+                    "-f",
+                    "null",
+                    "-",
+                ])?;
+                let measured = parse_loudnorm_stats(&log)?;
+                println!(
+                    "Measured: {} LUFS integrated, {} dBTP true peak, {} LU range",
+                    measured.input_i, measured.input_tp, measured.input_lra
+                );
+                self.loudnorm_filter(Some(&measured))?
+            }
+        };
+
+        let output_ext = ext_by_filename(output.to_str().unwrap())
+            .unwrap_or_default()
+            .to_lowercase();
+        let keep_streams = is_video && audio_codec_by_ext(&output_ext).is_none();
+        let codec = match output_ext.as_str() {
+            "webm" => "libopus",
+            ext => audio_codec_by_ext(ext).unwrap_or("aac"),
+        };
+
+        let mut args = vec!["-i".to_string(), self.input.to_str().unwrap().to_string()];
+        args.extend(normalize_output_args(
+            &filter,
+            &sample_rate,
+            codec,
+            keep_streams,
+        ));
+        args.extend(["-y".to_string(), output.to_str().unwrap().to_string()]);
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let log = run_ffmpeg_command_with_log(&args)?;
+
+        match self.peak {
+            Some(_) => println!("Peak after: {:.1} dBFS", measure_peak(&output)?),
+            None => {
+                let result = parse_loudnorm_stats(&log)?;
+                println!(
+                    "Result: {} LUFS integrated, {} dBTP true peak",
+                    result.output_i, result.output_tp
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use clap::{Parser, Subcommand};
 use omu::audio;
 use omu::convert;
 use omu::image;
+use omu::meta;
 use omu::video;
 
 #[derive(Parser)]
@@ -32,6 +33,9 @@ pub enum Commands {
 
     /// Audio manipulation utilities
     Audio(AudioCommandWrapper),
+
+    /// Tags, cover art and chapters
+    Meta(MetaCommandWrapper),
 }
 
 impl Commands {
@@ -41,6 +45,7 @@ impl Commands {
             Self::Video(cmd) => cmd.execute(),
             Self::Image(cmd) => cmd.execute(),
             Self::Audio(cmd) => cmd.execute(),
+            Self::Meta(cmd) => cmd.execute(),
         }
     }
 }
@@ -83,3 +88,16 @@ impl AudioCommandWrapper {
         self.command.execute()
     }
 }
+
+// Wrapper para MetaCommand
+#[derive(Parser)]
+pub struct MetaCommandWrapper {
+    #[command(subcommand)]
+    pub command: meta::MetaCommand,
+}
+
+impl MetaCommandWrapper {
+    fn execute(&self) -> anyhow::Result<()> {
+        self.command.execute()
+    }
+}
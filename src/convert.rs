@@ -1,8 +1,15 @@
 use anyhow::Result;
 use clap::Args;
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::utils::{ext_by_filename, file_type_from_extension, get_file_arg, run_ffmpeg_command};
+use crate::image::Rect;
+use crate::utils::{
+    ext_by_filename, file_type_from_extension, format_size, get_file_arg, run_ffmpeg_command,
+    FileType,
+};
 
 #[derive(Args)]
 pub struct ConvertArgs {
@@ -17,6 +24,22 @@ pub struct ConvertArgs {
     /// Additional FFmpeg parameters
     #[arg(short, long)]
     pub extra_args: Option<String>,
+
+    /// Encode for screen recordings: drop duplicate frames and tune for flat content and text
+    #[arg(long)]
+    pub screen: bool,
+
+    /// x264 tune for screen mode: stillimage (slides, documents) or animation (UI motion)
+    #[arg(long, default_value = "stillimage", requires = "screen")]
+    pub tune: String,
+
+    /// Keep small text crisp (full chroma, higher quality; larger files and less compatible)
+    #[arg(long, requires = "screen")]
+    pub sharp_text: bool,
+
+    /// Region (WxH+X+Y) encoded at higher quality, e.g. around the cursor or an active window
+    #[arg(long, requires = "screen")]
+    pub cursor_region: Option<Rect>,
 }
 
 impl ConvertArgs {
@@ -25,11 +48,28 @@ impl ConvertArgs {
         let file_type = file_type_from_extension(ext.as_str())?;
         let output = get_file_arg(file_type.clone(), &self.output)?;
 
-        convert_file(&self.input, &output, self.extra_args.as_deref())
+        if !self.screen {
+            return convert_file(&self.input, &output, self.extra_args.as_deref());
+        }
+
+        if !matches!(file_type, FileType::Video) {
+            anyhow::bail!("Screen mode needs a video input");
+        }
+        let options = ScreenOptions {
+            tune: self.tune.clone(),
+            sharp_text: self.sharp_text,
+            cursor_region: self.cursor_region,
+        };
+        convert_screen_recording(&self.input, &output, &options, self.extra_args.as_deref())?;
+
+        let input_size = fs::metadata(&self.input)?.len();
+        let output_size = fs::metadata(&output)?.len();
+        println!("{}", size_report(input_size, output_size));
+        Ok(())
     }
 }
 
-pub fn convert_file(input: &Path, output: &PathBuf, extra_args: Option<&str>) -> Result<()> {
+pub fn convert_file(input: &Path, output: &Path, extra_args: Option<&str>) -> Result<()> {
     let mut args = vec![
         "-i",
         input.to_str().unwrap(),
@@ -43,3 +83,97 @@ pub fn convert_file(input: &Path, output: &PathBuf, extra_args: Option<&str>) ->
     args.push(output.to_str().unwrap());
     run_ffmpeg_command(&args)
 }
+
+/// Encoding choices for screen recordings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScreenOptions {
+    pub tune: String,
+    pub sharp_text: bool,
+    pub cursor_region: Option<Rect>,
+}
+
+impl ScreenOptions {
+    /// Encoder arguments (everything between input and output).
+    pub fn ffmpeg_args(&self) -> Result<Vec<String>> {
+        if !matches!(self.tune.as_str(), "stillimage" | "animation") {
+            anyhow::bail!("Invalid tune. Use stillimage or animation");
+        }
+
+        // Frames identical to the previous one are dropped and the output
+        // keeps variable timestamps, so idle stretches cost almost nothing
+        let mut filter = "mpdecimate".to_string();
+        if let Some(region) = &self.cursor_region {
+            filter.push_str(&format!(
+                ",addroi=x={}:y={}:w={}:h={}:qoffset=-0.4",
+                region.x, region.y, region.width, region.height
+            ));
+        }
+
+        let (crf, pix_fmt) = if self.sharp_text {
+            ("14", "yuv444p")
+        } else {
+            ("22", "yuv420p")
+        };
+
+        let args = [
+            "-vf",
+            &filter,
+            "-fps_mode",
+            "vfr",
+            "-c:v",
+            "libx264",
+            "-preset",
+            "slow",
+            "-tune",
+            &self.tune,
+            "-crf",
+            crf,
+            "-pix_fmt",
+            pix_fmt,
+            "-c:a",
+            "aac",
+            "-b:a",
+            "128k",
+        ];
+        Ok(args.iter().map(|s| s.to_string()).collect())
+    }
+}
+
+pub fn convert_screen_recording(
+    input: &Path,
+    output: &Path,
+    options: &ScreenOptions,
+    extra_args: Option<&str>,
+) -> Result<()> {
+    let encode = options.ffmpeg_args()?;
+    let mut args = vec!["-i", input.to_str().unwrap()];
+    args.extend(encode.iter().map(|s| s.as_str()));
+    if let Some(extra) = extra_args {
+        args.extend(extra.split_whitespace());
+    }
+    args.extend(["-y", output.to_str().unwrap()]);
+    run_ffmpeg_command(&args)
+}
+
+/// Compare file sizes before and after conversion.
+pub fn size_report(input: u64, output: u64) -> String {
+    let change = if input == 0 {
+        String::new()
+    } else if output <= input {
+        format!(
+            " ({:.1}% smaller)",
+            (input - output) as f64 / input as f64 * 100.0
+        )
+    } else {
+        format!(
+            " ({:.1}% larger)",
+            (output - input) as f64 / input as f64 * 100.0
+        )
+    };
+    format!(
+        "Input: {}, output: {}{}",
+        format_size(input),
+        format_size(output),
+        change
+    )
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use image::DynamicImage;
+use std::str::FromStr;
+
+use crate::image::{apply_blur, apply_brightness, apply_contrast, apply_grayscale};
+
+/// A filter shared by image and video commands. Parameters mean the same on
+/// both: brightness, contrast, saturation and gamma are factors where `1.0`
+/// leaves the picture unchanged, blur is a gaussian sigma in pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Grayscale,
+    Invert,
+    Brightness(f32),
+    Contrast(f32),
+    Saturation(f32),
+    Gamma(f32),
+    Blur(f32),
+}
+
+impl Filter {
+    /// Parse a single `name` or `name=value`; filters that take a value fall
+    /// back to `default` (or `1.0`) when none is given.
+    pub fn parse(spec: &str, default: Option<f32>) -> Result<Self> {
+        let (name, value) = match spec.split_once('=') {
+            Some((name, value)) => {
+                let value: f32 = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid value for {}: {}", name, value))?;
+                (name.trim(), value)
+            }
+            None => (spec.trim(), default.unwrap_or(1.0)),
+        };
+
+        let filter = match name {
+            "grayscale" => Self::Grayscale,
+            "invert" => Self::Invert,
+            "brightness" => Self::Brightness(value),
+            "contrast" => Self::Contrast(value),
+            "saturation" => Self::Saturation(value),
+            "gamma" if value > 0.0 => Self::Gamma(value),
+            "gamma" => anyhow::bail!("Gamma must be greater than 0"),
+            "blur" => Self::Blur(value.max(0.0)),
+            _ => anyhow::bail!(
+                "Invalid filter type: {}. Use grayscale, invert, brightness, contrast, saturation, gamma, or blur",
+                name
+            ),
+        };
+        Ok(filter)
+    }
+
+    /// Parse a comma separated chain such as `grayscale,blur=2,contrast=1.2`.
+    pub fn parse_chain(spec: &str, default: Option<f32>) -> Result<Vec<Self>> {
+        let chain = spec
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| Self::parse(s, default))
+            .collect::<Result<Vec<_>>>()?;
+        if chain.is_empty() {
+            anyhow::bail!("No filters given");
+        }
+        Ok(chain)
+    }
+
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        match self {
+            Self::Grayscale => apply_grayscale(img),
+            Self::Invert => {
+                let mut img = img.clone();
+                img.invert();
+                img
+            }
+            Self::Brightness(factor) => apply_brightness(img, *factor),
+            Self::Contrast(factor) => apply_contrast(img, *factor),
+            Self::Saturation(factor) => map_rgb(img, |[r, g, b]| {
+                let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+                [r, g, b].map(|c| luma + (c - luma) * factor)
+            }),
+            Self::Gamma(gamma) => map_rgb(img, |rgb| {
+                rgb.map(|c| 255.0 * (c / 255.0).powf(1.0 / gamma))
+            }),
+            Self::Blur(sigma) => apply_blur(img, *sigma),
+        }
+    }
+
+    /// FFmpeg filter producing the same effect on video frames.
+    pub fn to_ffmpeg(&self) -> String {
+        match self {
+            Self::Grayscale => "hue=s=0".to_string(),
+            Self::Invert => "negate".to_string(),
+            Self::Brightness(factor) => format!(
+                "colorchannelmixer=rr={}:gg={}:bb={}",
+                factor, factor, factor
+            ),
+            Self::Contrast(factor) => format!("eq=contrast={}", factor),
+            Self::Saturation(factor) => format!("eq=saturation={}", factor),
+            Self::Gamma(gamma) => format!("eq=gamma={}", gamma),
+            Self::Blur(sigma) => format!("gblur=sigma={}", sigma),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s, None)
+    }
+}
+
+/// Apply a filter chain to an image, in order.
+pub fn apply_filters(img: &DynamicImage, filters: &[Filter]) -> DynamicImage {
+    filters
+        .iter()
+        .fold(img.clone(), |img, filter| filter.apply(&img))
+}
+
+/// Render a filter chain as an FFmpeg filtergraph fragment.
+pub fn filters_to_ffmpeg(filters: &[Filter]) -> String {
+    filters
+        .iter()
+        .map(Filter::to_ffmpeg)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn map_rgb(img: &DynamicImage, f: impl Fn([f32; 3]) -> [f32; 3]) -> DynamicImage {
+    let mut img = img.to_rgba8();
+    for pixel in img.pixels_mut() {
+        let [r, g, b, _] = pixel.0;
+        let rgb = f([r as f32, g as f32, b as f32]);
+        for (channel, value) in pixel.0.iter_mut().zip(rgb) {
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(img)
+}
@@ -1,9 +1,17 @@
 use anyhow::Result;
 use clap::{Args, Subcommand};
 use image::{imageops, DynamicImage, GenericImageView, RgbImage, RgbaImage};
-use std::path::PathBuf;
-
-use crate::utils::{get_file_arg, FileType};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::audio::afade_filter;
+use crate::filters::{apply_filters, Filter};
+use crate::utils::{
+    file_type_from_extension, get_file_arg, parse_dimensions, run_ffmpeg_command, FileType,
+};
 
 #[derive(Subcommand)]
 pub enum ImageCommand {
@@ -21,6 +29,12 @@ pub enum ImageCommand {
 
     /// Create a video from an image
     CreateVideo(CreateVideoArgs),
+
+    /// Blur or pixelate rectangular regions
+    Redact(RedactArgs),
+
+    /// Create a slideshow video from many images
+    Slideshow(SlideshowArgs),
 }
 
 impl ImageCommand {
@@ -31,6 +45,8 @@ impl ImageCommand {
             Self::Filter(cmd) => cmd.execute(),
             Self::Reshape(cmd) => cmd.execute(),
             Self::CreateVideo(cmd) => cmd.execute(),
+            Self::Redact(cmd) => cmd.execute(),
+            Self::Slideshow(cmd) => cmd.execute(),
         }
     }
 }
@@ -200,23 +216,18 @@ pub struct FilterArgs {
     #[arg(
         short,
         long,
-        help = "Filter type: grayscale, brightness, contrast, blur"
+        help = "Filter or chain: grayscale, invert, brightness, contrast, saturation, gamma, blur (e.g. grayscale,blur=2,contrast=1.2)"
     )]
     pub filter: String,
-    #[arg(long, help = "Filter intensity (for brightness, contrast, blur)")]
+    #[arg(long, help = "Filter intensity, used by filters given without a value")]
     pub intensity: Option<f32>,
 }
 
 impl FilterArgs {
     pub fn execute(&self) -> Result<()> {
+        let filters = Filter::parse_chain(&self.filter, self.intensity)?;
         let img = image::open(&self.input)?;
-        let result = match self.filter.as_str() {
-            "grayscale" => apply_grayscale(&img),
-            "brightness" => apply_brightness(&img, self.intensity.unwrap_or(1.0)),
-            "contrast" => apply_contrast(&img, self.intensity.unwrap_or(1.0)),
-            "blur" => apply_blur(&img, self.intensity.unwrap_or(1.0)),
-            _ => anyhow::bail!("Invalid filter type."),
-        };
+        let result = apply_filters(&img, &filters);
 
         // Get output or prompt for one
         let output = get_file_arg(FileType::Image, &self.output)?;
@@ -243,9 +254,7 @@ pub fn apply_contrast(img: &DynamicImage, factor: f32) -> DynamicImage {
     let mut img = img.to_rgba8();
     for pixel in img.pixels_mut() {
         for channel in pixel.0.iter_mut().take(3) {
-            *channel = ((*channel as f32 - 128.0) * factor + 128.0)
-                .min(255.0)
-                .max(0.0) as u8;
+            *channel = ((*channel as f32 - 128.0) * factor + 128.0).clamp(0.0, 255.0) as u8;
         }
     }
     DynamicImage::ImageRgba8(img)
@@ -366,25 +375,458 @@ impl CreateVideoArgs {
     }
 }
 
-pub fn create_video_from_image(img: &DynamicImage, output: &PathBuf, duration: u32) -> Result<()> {
+pub fn create_video_from_image(img: &DynamicImage, output: &Path, duration: u32) -> Result<()> {
+    create_video_from_still(StillSource::Image(img), output, duration as f64, None)
+}
+
+/// Picture looped as video input 0 by `create_video_from_still`.
+pub enum StillSource<'a> {
+    Image(&'a DynamicImage),
+    /// A solid FFmpeg colour (`navy`, `0x112233`) of the given size
+    Color {
+        color: &'a str,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Audio added as input 1 by `create_video_from_still`, with a filter graph
+/// over `[0:v]` and `[1:a]` that writes `[v]`.
+pub struct AudioTrack<'a> {
+    pub audio: &'a Path,
+    pub filter_complex: &'a str,
+}
+
+/// Encode `source` as an H.264 video of `duration` seconds, optionally with
+/// an audio track and a filter graph combining both.
+pub fn create_video_from_still(
+    source: StillSource,
+    output: &Path,
+    duration: f64,
+    audio: Option<AudioTrack>,
+) -> Result<()> {
     let temp_dir = tempfile::tempdir()?;
-    let frame_path = temp_dir.path().join("frame.png");
-    img.save(&frame_path)?;
-
-    let args = [
-        "-loop",
-        "1",
-        "-i",
-        frame_path.to_str().unwrap(),
-        "-c:v",
-        "libx264",
-        "-t",
-        &duration.to_string(),
-        "-pix_fmt",
-        "yuv420p",
-        "-y",
-        output.to_str().unwrap(),
-    ];
-
-    crate::utils::run_ffmpeg_command(&args)
+    let mut args: Vec<String> = Vec::new();
+    match source {
+        StillSource::Image(img) => {
+            let frame_path = temp_dir.path().join("frame.png");
+            img.save(&frame_path)?;
+            args.extend(["-loop".into(), "1".into()]);
+            args.extend(["-i".into(), frame_path.to_str().unwrap().into()]);
+        }
+        StillSource::Color {
+            color,
+            width,
+            height,
+        } => {
+            args.extend(["-f".into(), "lavfi".into()]);
+            args.extend([
+                "-i".into(),
+                format!("color=c={}:s={}x{}", color, width, height),
+            ]);
+        }
+    }
+    if let Some(track) = audio {
+        args.extend(["-i".into(), track.audio.to_str().unwrap().into()]);
+        args.extend(["-filter_complex".into(), track.filter_complex.into()]);
+        args.extend(["-map".into(), "[v]".into(), "-map".into(), "1:a".into()]);
+        args.extend(["-c:a".into(), "aac".into()]);
+    }
+    args.extend([
+        "-c:v".into(),
+        "libx264".into(),
+        "-t".into(),
+        format!("{:.3}", duration),
+        "-pix_fmt".into(),
+        "yuv420p".into(),
+        "-y".into(),
+        output.to_str().unwrap().into(),
+    ]);
+
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    run_ffmpeg_command(&args)
+}
+
+/// A rectangle given as `WxH+X+Y`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl FromStr for Rect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid rectangle: {}. Use WxH+X+Y", s);
+        let mut parts = s.trim().split('+');
+        let (size, x, y) = (parts.next(), parts.next(), parts.next());
+        let (Some(size), Some(x), Some(y), None) = (size, x, y, parts.next()) else {
+            return Err(invalid());
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let rect = Self {
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+            x: x.parse().map_err(|_| invalid())?,
+            y: y.parse().map_err(|_| invalid())?,
+        };
+        if rect.width == 0 || rect.height == 0 {
+            anyhow::bail!("Rectangle must not be empty: {}", s);
+        }
+        Ok(rect)
+    }
+}
+
+impl Rect {
+    /// Clip to a `width`x`height` frame, or `None` if nothing is left.
+    pub fn clip(&self, width: u32, height: u32) -> Option<Self> {
+        if self.x >= width || self.y >= height {
+            return None;
+        }
+        Some(Self {
+            width: self.width.min(width - self.x),
+            height: self.height.min(height - self.y),
+            x: self.x,
+            y: self.y,
+        })
+    }
+}
+
+/// Blur (`sigma`) or pixelate (`strength` is the block size) a region in place.
+pub fn redact_image(
+    img: &DynamicImage,
+    rect: Rect,
+    method: &str,
+    strength: f32,
+) -> Result<DynamicImage> {
+    let (width, height) = img.dimensions();
+    let rect = rect
+        .clip(width, height)
+        .ok_or_else(|| anyhow::anyhow!("Region {:?} is outside the image", rect))?;
+    let region = DynamicImage::ImageRgba8(
+        imageops::crop_imm(img, rect.x, rect.y, rect.width, rect.height).to_image(),
+    );
+
+    // A zero sigma or one-pixel blocks would leave the region readable
+    let redacted = match method {
+        "blur" if strength <= 0.0 => anyhow::bail!("Blur strength must be greater than 0"),
+        "pixelate" if strength < 2.0 => anyhow::bail!("Pixelate strength must be at least 2"),
+        "blur" => apply_blur(&region, strength),
+        "pixelate" => {
+            let block = strength;
+            let small = region.resize_exact(
+                ((rect.width as f32 / block).ceil() as u32).max(1),
+                ((rect.height as f32 / block).ceil() as u32).max(1),
+                imageops::FilterType::Triangle,
+            );
+            small.resize_exact(rect.width, rect.height, imageops::FilterType::Nearest)
+        }
+        _ => anyhow::bail!("Invalid redaction method. Use blur or pixelate"),
+    };
+
+    let mut result = img.to_rgba8();
+    imageops::overlay(
+        &mut result,
+        &redacted.to_rgba8(),
+        rect.x as i64,
+        rect.y as i64,
+    );
+    Ok(DynamicImage::ImageRgba8(result))
+}
+
+#[derive(Args)]
+pub struct RedactArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "Region to redact as WxH+X+Y (repeatable)"
+    )]
+    pub region: Vec<Rect>,
+    #[arg(
+        short,
+        long,
+        default_value = "blur",
+        help = "Redaction method: blur or pixelate"
+    )]
+    pub method: String,
+    #[arg(
+        short,
+        long,
+        help = "Blur sigma or pixelation block size (default 10 / 16)"
+    )]
+    pub strength: Option<f32>,
+}
+
+impl RedactArgs {
+    pub fn execute(&self) -> Result<()> {
+        let strength = self.strength.unwrap_or(if self.method == "pixelate" {
+            16.0
+        } else {
+            10.0
+        });
+        let mut img = image::open(&self.input)?;
+        for rect in &self.region {
+            img = redact_image(&img, *rect, &self.method, strength)?;
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Image, &self.output)?;
+        img.save(&output)?;
+        Ok(())
+    }
+}
+
+/// Expand directories into the images they contain (sorted by name).
+pub fn collect_images(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut images = Vec::new();
+    for input in inputs {
+        if !input.is_dir() {
+            images.push(input.clone());
+            continue;
+        }
+
+        let mut entries: Vec<PathBuf> = fs::read_dir(input)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| {
+                        matches!(
+                            file_type_from_extension(&ext.to_lowercase()),
+                            Ok(FileType::Image)
+                        )
+                    })
+                    .unwrap_or(false)
+            })
+            .collect();
+        entries.sort();
+        images.extend(entries);
+    }
+
+    if images.is_empty() {
+        anyhow::bail!("No images found");
+    }
+    Ok(images)
+}
+
+#[derive(Args)]
+pub struct SlideshowArgs {
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "Images or directories of images, in order"
+    )]
+    pub inputs: Vec<PathBuf>,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        default_value = "3",
+        help = "Seconds per image; a list (3,5,2) sets each image, the last value repeats"
+    )]
+    pub duration: Vec<f64>,
+    #[arg(long, default_value = "1920x1080", help = "Output size WxH")]
+    pub size: String,
+    #[arg(long, default_value_t = 30, help = "Output frame rate")]
+    pub fps: u32,
+    #[arg(
+        long,
+        default_value = "fit",
+        help = "Fit mode: fit (letterbox) or fill (crop to fill the frame)"
+    )]
+    pub fit: String,
+    #[arg(
+        short,
+        long,
+        default_value_t = 0.0,
+        help = "Crossfade duration in seconds between images"
+    )]
+    pub transition: f64,
+    #[arg(long, help = "Slowly zoom in and out of each image (Ken Burns effect)")]
+    pub ken_burns: bool,
+    #[arg(
+        short,
+        long,
+        help = "Background music, looped or trimmed to the slideshow length"
+    )]
+    pub audio: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "Music fade-out in seconds at the end (0 to disable)"
+    )]
+    pub audio_fade: f64,
+}
+
+impl SlideshowArgs {
+    /// Duration of each of `count` images.
+    pub fn durations(&self, count: usize) -> Result<Vec<f64>> {
+        let last = *self
+            .duration
+            .last()
+            .ok_or_else(|| anyhow::anyhow!("No duration given"))?;
+        let durations: Vec<f64> = (0..count)
+            .map(|i| self.duration.get(i).copied().unwrap_or(last))
+            .collect();
+
+        for duration in &durations {
+            if *duration <= self.transition {
+                anyhow::bail!(
+                    "Each image must last longer than the transition ({}s)",
+                    self.transition
+                );
+            }
+        }
+        Ok(durations)
+    }
+
+    /// Total length; each crossfade overlaps two images.
+    pub fn total_duration(&self, durations: &[f64]) -> f64 {
+        durations.iter().sum::<f64>() - self.transition * durations.len().saturating_sub(1) as f64
+    }
+
+    /// Filter graph over one input per image (plus the music, if any,
+    /// as the last input), writing `[v]` and `[a]`.
+    pub fn filter_graph(&self, durations: &[f64]) -> Result<String> {
+        let (width, height) = parse_dimensions(&self.size)?;
+        let fit = match self.fit.as_str() {
+            "fit" => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+                w = width,
+                h = height
+            ),
+            "fill" => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}",
+                w = width,
+                h = height
+            ),
+            _ => anyhow::bail!("Invalid fit mode. Use fit or fill"),
+        };
+
+        let mut graph = Vec::new();
+        for (i, duration) in durations.iter().enumerate() {
+            let motion = if self.ken_burns {
+                // Zoom in on even images and out on odd ones, from a 2x
+                // upscale to avoid jittery sub-pixel steps
+                let frames = (duration * self.fps as f64).round() as u32;
+                let zoom = if i % 2 == 0 {
+                    format!("1+0.2*on/{}", frames)
+                } else {
+                    format!("1.2-0.2*on/{}", frames)
+                };
+                format!(
+                    ",scale={}:{},zoompan=z='{}':d={}:x='iw/2-(iw/zoom/2)':y='ih/2-(ih/zoom/2)':s={}x{}:fps={}",
+                    width * 2,
+                    height * 2,
+                    zoom,
+                    frames,
+                    width,
+                    height,
+                    self.fps
+                )
+            } else {
+                String::new()
+            };
+            graph.push(format!(
+                "[{}:v]{}{},setsar=1,fps={},format=yuv420p,settb=AVTB[s{}]",
+                i, fit, motion, self.fps, i
+            ));
+        }
+
+        if self.transition > 0.0 && durations.len() > 1 {
+            let mut current = "s0".to_string();
+            let mut offset = 0.0;
+            for i in 1..durations.len() {
+                offset += durations[i - 1] - self.transition;
+                let next = if i + 1 == durations.len() {
+                    "v".to_string()
+                } else {
+                    format!("x{}", i)
+                };
+                graph.push(format!(
+                    "[{}][s{}]xfade=transition=fade:duration={}:offset={:.3}[{}]",
+                    current, i, self.transition, offset, next
+                ));
+                current = next;
+            }
+        } else {
+            let inputs: String = (0..durations.len()).map(|i| format!("[s{}]", i)).collect();
+            graph.push(format!("{}concat=n={}:v=1:a=0[v]", inputs, durations.len()));
+        }
+
+        if self.audio.is_some() {
+            let total = self.total_duration(durations);
+            let fade = (self.audio_fade > 0.0).then_some(self.audio_fade);
+            let mut audio = format!("[{}:a]atrim=0:{:.3}", durations.len(), total);
+            if let Some(fade) = afade_filter(None, fade, "tri", total) {
+                audio.push(',');
+                audio.push_str(&fade);
+            }
+            graph.push(audio + "[a]");
+        }
+        Ok(graph.join(";"))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let images = collect_images(&self.inputs)?;
+        let durations = self.durations(images.len())?;
+        let graph = self.filter_graph(&durations)?;
+        let total = self.total_duration(&durations);
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        let mut args: Vec<String> = Vec::new();
+        for (image, duration) in images.iter().zip(&durations) {
+            // Ken Burns renders all frames from a single input frame
+            if !self.ken_burns {
+                args.extend([
+                    "-loop".into(),
+                    "1".into(),
+                    "-framerate".into(),
+                    self.fps.to_string(),
+                    "-t".into(),
+                    duration.to_string(),
+                ]);
+            }
+            args.extend(["-i".into(), image.to_str().unwrap().into()]);
+        }
+        if let Some(audio) = &self.audio {
+            args.extend([
+                "-stream_loop".into(),
+                "-1".into(),
+                "-i".into(),
+                audio.to_str().unwrap().into(),
+            ]);
+        }
+
+        args.extend(["-filter_complex".into(), graph, "-map".into(), "[v]".into()]);
+        if self.audio.is_some() {
+            args.extend(["-map".into(), "[a]".into()]);
+        }
+        args.extend([
+            "-c:v".into(),
+            "libx264".into(),
+            "-pix_fmt".into(),
+            "yuv420p".into(),
+            "-t".into(),
+            format!("{:.3}", total),
+            "-y".into(),
+            output.to_str().unwrap().into(),
+        ]);
+
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run_ffmpeg_command(&args)
+    }
 }
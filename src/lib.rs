@@ -1,6 +1,11 @@
 pub mod audio;
 pub mod convert;
+pub mod filters;
 pub mod image;
+pub mod meta;
+pub mod packaging;
+pub mod probe;
+pub mod subtitles;
 pub mod utils;
 pub mod video;
 
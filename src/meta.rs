@@ -0,0 +1,578 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tempfile::NamedTempFile;
+
+use crate::probe::{parse_rational, probe, MediaInfo, StreamInfo};
+use crate::utils::{
+    ext_by_filename, file_type_from_extension, format_time, get_file_arg, parse_time,
+    run_ffmpeg_command, FileType,
+};
+
+#[derive(Subcommand)]
+pub enum MetaCommand {
+    /// Show tags, cover art and chapters
+    Get(GetMetaArgs),
+
+    /// Set tags, cover art and chapters
+    Set(SetMetaArgs),
+
+    /// Remove tags, cover art and chapters
+    Strip(StripMetaArgs),
+
+    /// Export chapters as FFMETADATA, text or JSON
+    Chapters(ChaptersArgs),
+}
+
+impl MetaCommand {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            Self::Get(cmd) => cmd.execute(),
+            Self::Set(cmd) => cmd.execute(),
+            Self::Strip(cmd) => cmd.execute(),
+            Self::Chapters(cmd) => cmd.execute(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChapterFormat {
+    Ffmetadata,
+    Text,
+    Json,
+}
+
+impl FromStr for ChapterFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ffmetadata" | "ffmeta" | "meta" | "ini" => Ok(Self::Ffmetadata),
+            "text" | "txt" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => anyhow::bail!(
+                "Invalid chapter format: {}. Use ffmetadata, text, or json",
+                s
+            ),
+        }
+    }
+}
+
+impl ChapterFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = ext_by_filename(path.to_str().unwrap()).unwrap_or_default();
+        ext.parse()
+    }
+}
+
+/// Chapters stored in a media file, as reported by ffprobe.
+pub fn media_chapters(info: &MediaInfo) -> Vec<Chapter> {
+    info.chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| Chapter {
+            start: chapter.start_time.parse().unwrap_or(0.0),
+            end: chapter.end_time.parse().unwrap_or(0.0),
+            title: tag_value(&chapter.tags, "title")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+        })
+        .collect()
+}
+
+/// Look up a tag ignoring case (Matroska and Ogg store keys in upper case).
+pub fn tag_value<'a>(tags: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Escape a value for an FFMETADATA file.
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape_ffmetadata(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Split an FFMETADATA `key=value` line at the first unescaped `=`.
+fn split_ffmetadata_line(line: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '=' if !escaped => return Some((&line[..i], &line[i + 1..])),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+pub fn write_chapters(chapters: &[Chapter], format: ChapterFormat) -> Result<String> {
+    let content = match format {
+        ChapterFormat::Ffmetadata => {
+            let mut content = ";FFMETADATA1\n".to_string();
+            for chapter in chapters {
+                content.push_str(&format!(
+                    "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+                    (chapter.start * 1000.0).round() as u64,
+                    (chapter.end * 1000.0).round() as u64,
+                    escape_ffmetadata(&chapter.title)
+                ));
+            }
+            content
+        }
+        ChapterFormat::Text => chapters
+            .iter()
+            .map(|chapter| format!("{} {}\n", format_time(chapter.start), chapter.title))
+            .collect(),
+        ChapterFormat::Json => serde_json::to_string_pretty(chapters)? + "\n",
+    };
+    Ok(content)
+}
+
+/// Parse chapters. Text chapters only carry start times, so each one ends
+/// where the next starts and the last ends at `duration` (if known).
+pub fn parse_chapters(
+    content: &str,
+    format: ChapterFormat,
+    duration: Option<f64>,
+) -> Result<Vec<Chapter>> {
+    let chapters = match format {
+        ChapterFormat::Json => {
+            serde_json::from_str(content).context("Failed to parse JSON chapters")?
+        }
+        ChapterFormat::Ffmetadata => parse_ffmetadata_chapters(content)?,
+        ChapterFormat::Text => {
+            let mut chapters: Vec<Chapter> = Vec::new();
+            for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                let (time, title) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                let start = parse_time(time)?;
+                if let Some(previous) = chapters.last_mut() {
+                    previous.end = start;
+                }
+                chapters.push(Chapter {
+                    start,
+                    end: start,
+                    title: title.trim().to_string(),
+                });
+            }
+            if let (Some(last), Some(duration)) = (chapters.last_mut(), duration) {
+                last.end = duration.max(last.start);
+            }
+            chapters
+        }
+    };
+
+    for chapter in &chapters {
+        if chapter.end < chapter.start {
+            anyhow::bail!("Chapter \"{}\" ends before it starts", chapter.title);
+        }
+    }
+    Ok(chapters)
+}
+
+fn parse_ffmetadata_chapters(content: &str) -> Result<Vec<Chapter>> {
+    let mut chapters = Vec::new();
+    // (timebase, start, end, title) of the chapter being read
+    let mut current: Option<(f64, i64, i64, String)> = None;
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with('[') {
+            if let Some((timebase, start, end, title)) = current.take() {
+                chapters.push(Chapter {
+                    start: start as f64 * timebase,
+                    end: end as f64 * timebase,
+                    title,
+                });
+            }
+            if line == "[CHAPTER]" {
+                current = Some((0.001, 0, 0, format!("Chapter {}", chapters.len() + 1)));
+            }
+            continue;
+        }
+        if line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        let (Some(chapter), Some((key, value))) = (current.as_mut(), split_ffmetadata_line(line))
+        else {
+            continue;
+        };
+        match key {
+            "TIMEBASE" => {
+                chapter.0 =
+                    parse_rational(value).with_context(|| format!("Invalid TIMEBASE: {}", value))?
+            }
+            "START" => chapter.1 = value.parse().context("Invalid chapter START")?,
+            "END" => chapter.2 = value.parse().context("Invalid chapter END")?,
+            "title" => chapter.3 = unescape_ffmetadata(value),
+            _ => {}
+        }
+    }
+
+    if let Some((timebase, start, end, title)) = current {
+        chapters.push(Chapter {
+            start: start as f64 * timebase,
+            end: end as f64 * timebase,
+            title,
+        });
+    }
+    Ok(chapters)
+}
+
+/// A custom tag given as `key=value`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Tag {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Self {
+                key: key.trim().to_string(),
+                value: value.to_string(),
+            }),
+            _ => anyhow::bail!("Invalid tag: {}. Use key=value", s),
+        }
+    }
+}
+
+/// Output file type matching the input, used when prompting for an output.
+fn file_type_of(path: &Path) -> FileType {
+    ext_by_filename(path.to_str().unwrap())
+        .and_then(|ext| file_type_from_extension(&ext.to_lowercase()).ok())
+        .unwrap_or(FileType::Video)
+}
+
+/// Whether a stream holds cover art: an attached picture, or a Matroska
+/// attachment that is an image or named `cover*`. Other attachments, such as
+/// fonts used by ASS subtitles, are not.
+pub fn is_cover_art(stream: &StreamInfo) -> bool {
+    match stream.codec_type.as_deref() {
+        Some("attachment") => {
+            tag_value(&stream.tags, "mimetype").is_some_and(|m| m.starts_with("image/"))
+                || tag_value(&stream.tags, "filename")
+                    .is_some_and(|f| f.to_lowercase().starts_with("cover"))
+        }
+        _ => stream.disposition.get("attached_pic") == Some(&1),
+    }
+}
+
+#[derive(Args)]
+pub struct GetMetaArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Print only this tag's value")]
+    pub key: Option<String>,
+}
+
+impl GetMetaArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+
+        if let Some(key) = &self.key {
+            let value = tag_value(&info.format.tags, key)
+                .ok_or_else(|| anyhow::anyhow!("Tag not found: {}", key))?;
+            println!("{}", value);
+            return Ok(());
+        }
+
+        let mut tags: Vec<_> = info.format.tags.iter().collect();
+        tags.sort();
+        for (key, value) in tags {
+            println!("{}={}", key, value);
+        }
+
+        let covers = info.streams.iter().filter(|s| is_cover_art(s)).count();
+        if covers > 0 {
+            println!("Cover art: {} image(s)", covers);
+        }
+
+        let chapters = media_chapters(&info);
+        if !chapters.is_empty() {
+            println!("Chapters:");
+            print!("{}", write_chapters(&chapters, ChapterFormat::Text)?);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct SetMetaArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long)]
+    pub title: Option<String>,
+    #[arg(long)]
+    pub artist: Option<String>,
+    #[arg(long)]
+    pub album: Option<String>,
+    #[arg(long)]
+    pub date: Option<String>,
+    #[arg(long)]
+    pub comment: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "Custom tag as key=value (an empty value removes it)"
+    )]
+    pub tag: Vec<Tag>,
+    #[arg(long, help = "Cover art image, replacing any existing one")]
+    pub cover: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Chapters file to import (.ffmeta, .txt with HH:MM:SS Title lines, or .json)"
+    )]
+    pub chapters: Option<PathBuf>,
+}
+
+impl SetMetaArgs {
+    /// Named and custom tags, in the order they are applied.
+    pub fn tags(&self) -> Vec<Tag> {
+        let named = [
+            ("title", &self.title),
+            ("artist", &self.artist),
+            ("album", &self.album),
+            ("date", &self.date),
+            ("comment", &self.comment),
+        ];
+        named
+            .into_iter()
+            .filter_map(|(key, value)| {
+                value.as_ref().map(|value| Tag {
+                    key: key.to_string(),
+                    value: value.clone(),
+                })
+            })
+            .chain(self.tag.iter().cloned())
+            .collect()
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let tags = self.tags();
+        if tags.is_empty() && self.cover.is_none() && self.chapters.is_none() {
+            anyhow::bail!("Nothing to set. Use tag options, --cover or --chapters");
+        }
+
+        let info = probe(&self.input)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(file_type_of(&self.input), &self.output)?;
+        let output_ext = ext_by_filename(output.to_str().unwrap())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let mut args: Vec<String> = vec!["-i".into(), self.input.to_str().unwrap().into()];
+        let mut maps: Vec<String> = vec!["-map".into(), "0".into()];
+        let mut codecs: Vec<String> = vec!["-c".into(), "copy".into()];
+        let mut inputs = 1;
+
+        if let Some(cover) = &self.cover {
+            // Replace existing cover art instead of stacking images
+            for stream in info.streams.iter().filter(|s| is_cover_art(s)) {
+                maps.extend(["-map".into(), format!("-0:{}", stream.index)]);
+            }
+
+            if output_ext == "webm" {
+                anyhow::bail!("WebM does not support cover art attachments; use .mkv instead");
+            } else if output_ext == "mkv" {
+                // Matroska stores covers as attachments, after the ones we keep
+                let (mime, filename) = match ext_by_filename(cover.to_str().unwrap())
+                    .unwrap_or_default()
+                    .to_lowercase()
+                    .as_str()
+                {
+                    "png" => ("image/png", "cover.png"),
+                    _ => ("image/jpeg", "cover.jpg"),
+                };
+                let attachments = info
+                    .streams
+                    .iter()
+                    .filter(|s| s.codec_type.as_deref() == Some("attachment") && !is_cover_art(s))
+                    .count();
+                codecs.extend([
+                    "-attach".into(),
+                    cover.to_str().unwrap().into(),
+                    format!("-metadata:s:t:{}", attachments),
+                    format!("mimetype={}", mime),
+                    format!("-metadata:s:t:{}", attachments),
+                    format!("filename={}", filename),
+                ]);
+            } else {
+                let videos = info
+                    .streams
+                    .iter()
+                    .filter(|s| s.codec_type.as_deref() == Some("video") && !is_cover_art(s))
+                    .count();
+                args.extend(["-i".into(), cover.to_str().unwrap().into()]);
+                maps.extend(["-map".into(), format!("{}:v:0", inputs)]);
+                codecs.extend([
+                    format!("-disposition:v:{}", videos),
+                    "attached_pic".into(),
+                    format!("-metadata:s:v:{}", videos),
+                    "title=Cover".into(),
+                ]);
+                if output_ext == "mp3" {
+                    codecs.extend(["-id3v2_version".into(), "3".into()]);
+                }
+                inputs += 1;
+            }
+        }
+
+        // Chapters are converted to FFMETADATA and mapped from their own input
+        let mut chapters_file = NamedTempFile::new()?;
+        if let Some(path) = &self.chapters {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let chapters =
+                parse_chapters(&content, ChapterFormat::from_path(path)?, info.duration())?;
+            chapters_file
+                .write_all(write_chapters(&chapters, ChapterFormat::Ffmetadata)?.as_bytes())?;
+
+            args.extend([
+                "-f".into(),
+                "ffmetadata".into(),
+                "-i".into(),
+                chapters_file.path().to_str().unwrap().into(),
+            ]);
+            codecs.extend(["-map_chapters".into(), inputs.to_string()]);
+        }
+
+        for tag in &tags {
+            codecs.extend(["-metadata".into(), format!("{}={}", tag.key, tag.value)]);
+        }
+
+        args.extend(maps);
+        args.extend(codecs);
+        args.extend(["-y".into(), output.to_str().unwrap().into()]);
+
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct StripMetaArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long, help = "Keep chapters")]
+    pub keep_chapters: bool,
+    #[arg(long, help = "Keep cover art")]
+    pub keep_cover: bool,
+}
+
+impl StripMetaArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(file_type_of(&self.input), &self.output)?;
+
+        let mut args: Vec<String> = vec![
+            "-i".into(),
+            self.input.to_str().unwrap().into(),
+            "-map".into(),
+            "0".into(),
+        ];
+        if !self.keep_cover {
+            for stream in info.streams.iter().filter(|s| is_cover_art(s)) {
+                args.extend(["-map".into(), format!("-0:{}", stream.index)]);
+            }
+        }
+        args.extend([
+            "-c".into(),
+            "copy".into(),
+            "-map_metadata".into(),
+            "-1".into(),
+            // Don't write an encoder tag either
+            "-fflags".into(),
+            "+bitexact".into(),
+        ]);
+        if !self.keep_chapters {
+            args.extend(["-map_chapters".into(), "-1".into()]);
+        }
+        args.extend(["-y".into(), output.to_str().unwrap().into()]);
+
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct ChaptersArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Write chapters to a file instead of stdout")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Chapter format: ffmetadata, text, or json (default: from the output extension, or text)"
+    )]
+    pub format: Option<ChapterFormat>,
+}
+
+impl ChaptersArgs {
+    pub fn execute(&self) -> Result<()> {
+        let format = match (&self.format, &self.output) {
+            (Some(format), _) => *format,
+            (None, Some(output)) => ChapterFormat::from_path(output)?,
+            (None, None) => ChapterFormat::Text,
+        };
+
+        let chapters = media_chapters(&probe(&self.input)?);
+        if chapters.is_empty() {
+            anyhow::bail!("No chapters found in {}", self.input.display());
+        }
+
+        let content = write_chapters(&chapters, format)?;
+        match &self.output {
+            Some(output) => {
+                fs::write(output, content)?;
+                println!("Wrote {} chapters to {}", chapters.len(), output.display());
+            }
+            None => print!("{}", content),
+        }
+        Ok(())
+    }
+}
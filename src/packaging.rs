@@ -0,0 +1,365 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::probe::probe;
+use crate::utils::run_ffmpeg_command;
+
+/// One step of the bitrate ladder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rendition {
+    pub height: u32,
+    /// Video bitrate in kbit/s
+    pub video_bitrate: u32,
+    /// Audio bitrate in kbit/s
+    pub audio_bitrate: u32,
+}
+
+fn parse_kbps(value: &str) -> Result<u32> {
+    let value = value.trim().to_lowercase();
+    let number = value.strip_suffix('k').unwrap_or(&value);
+    number
+        .parse()
+        .with_context(|| format!("Invalid bitrate: {}", value))
+}
+
+impl FromStr for Rendition {
+    type Err = anyhow::Error;
+
+    /// `720p:2800k` or `720p:2800k:128k`
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let (height, video, audio) = match parts.as_slice() {
+            [height, video] => (height, video, "128k"),
+            [height, video, audio] => (height, video, *audio),
+            _ => anyhow::bail!(
+                "Invalid rendition: {} (expected HEIGHTp:VIDEOk[:AUDIOk])",
+                s
+            ),
+        };
+
+        Ok(Self {
+            height: height
+                .trim_end_matches('p')
+                .parse()
+                .with_context(|| format!("Invalid rendition height: {}", height))?,
+            video_bitrate: parse_kbps(video)?,
+            audio_bitrate: parse_kbps(audio)?,
+        })
+    }
+}
+
+pub fn default_ladder() -> Vec<Rendition> {
+    [(1080, 5000), (720, 2800), (480, 1400), (360, 800)]
+        .iter()
+        .map(|&(height, video_bitrate)| Rendition {
+            height,
+            video_bitrate,
+            audio_bitrate: 128,
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub bandwidth: u64,
+    pub resolution: Option<(u32, u32)>,
+    pub uri: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaPlaylist {
+    pub target_duration: f64,
+    pub segments: Vec<(f64, String)>,
+    pub ended: bool,
+}
+
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    attributes.split(',').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key.trim() == name).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Parse the variants of an HLS master playlist.
+pub fn parse_master_playlist(content: &str) -> Result<Vec<Variant>> {
+    let mut lines = content.lines().map(str::trim);
+    if lines.next() != Some("#EXTM3U") {
+        anyhow::bail!("Not an HLS playlist");
+    }
+
+    let mut variants = Vec::new();
+    while let Some(line) = lines.next() {
+        let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") else {
+            continue;
+        };
+        let uri = lines
+            .by_ref()
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .context("Variant without URI")?;
+
+        variants.push(Variant {
+            bandwidth: attribute(attributes, "BANDWIDTH")
+                .context("Variant without BANDWIDTH")?
+                .parse()?,
+            resolution: attribute(attributes, "RESOLUTION").and_then(|r| {
+                let (w, h) = r.split_once('x')?;
+                Some((w.parse().ok()?, h.parse().ok()?))
+            }),
+            uri: uri.to_string(),
+        });
+    }
+
+    Ok(variants)
+}
+
+/// Parse the segments of an HLS media playlist.
+pub fn parse_media_playlist(content: &str) -> Result<MediaPlaylist> {
+    let mut lines = content.lines().map(str::trim);
+    if lines.next() != Some("#EXTM3U") {
+        anyhow::bail!("Not an HLS playlist");
+    }
+
+    let mut playlist = MediaPlaylist {
+        target_duration: 0.0,
+        segments: Vec::new(),
+        ended: false,
+    };
+    let mut duration = None;
+
+    for line in lines {
+        if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            playlist.target_duration = value.parse()?;
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            duration = Some(value.split(',').next().unwrap_or("").parse()?);
+        } else if line == "#EXT-X-ENDLIST" {
+            playlist.ended = true;
+        } else if !line.is_empty() && !line.starts_with('#') {
+            let duration = duration.take().context("Segment without #EXTINF")?;
+            playlist.segments.push((duration, line.to_string()));
+        }
+    }
+
+    Ok(playlist)
+}
+
+/// Check that every variant and segment referenced by `master.m3u8` exists,
+/// returning the variants with their media playlists.
+pub fn verify_hls(dir: &Path) -> Result<Vec<(Variant, MediaPlaylist)>> {
+    let master = fs::read_to_string(dir.join("master.m3u8")).context("Missing master.m3u8")?;
+
+    let mut result = Vec::new();
+    for variant in parse_master_playlist(&master)? {
+        let playlist_path = dir.join(&variant.uri);
+        let playlist = parse_media_playlist(
+            &fs::read_to_string(&playlist_path)
+                .with_context(|| format!("Missing playlist {}", variant.uri))?,
+        )?;
+
+        let playlist_dir = playlist_path.parent().unwrap_or(dir);
+        for (_, segment) in &playlist.segments {
+            if !playlist_dir.join(segment).exists() {
+                anyhow::bail!("Missing segment {} of {}", segment, variant.uri);
+            }
+        }
+        result.push((variant, playlist));
+    }
+
+    if result.is_empty() {
+        anyhow::bail!("master.m3u8 has no variants");
+    }
+    Ok(result)
+}
+
+#[derive(Args)]
+pub struct PackageArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Output directory")]
+    pub output: PathBuf,
+    #[arg(long, help = "Write HLS playlists (default when --dash is not given)")]
+    pub hls: bool,
+    #[arg(
+        long,
+        help = "Write a DASH manifest (with --hls the ladder is encoded once per format, doubling encode time)"
+    )]
+    pub dash: bool,
+    #[arg(
+        short,
+        long,
+        help = "Rendition as HEIGHTp:VIDEOk[:AUDIOk], repeatable (default 1080p/720p/480p/360p)"
+    )]
+    pub rendition: Vec<Rendition>,
+    #[arg(long, default_value_t = 6, help = "Segment duration in seconds")]
+    pub segment_duration: u32,
+}
+
+impl PackageArgs {
+    /// Renditions to encode, skipping those taller than the source.
+    pub fn ladder(&self, source_height: Option<u32>) -> Vec<Rendition> {
+        let ladder = if self.rendition.is_empty() {
+            default_ladder()
+        } else {
+            self.rendition.clone()
+        };
+
+        let Some(source_height) = source_height else {
+            return ladder;
+        };
+        let mut fitting: Vec<Rendition> = ladder
+            .iter()
+            .filter(|r| r.height <= source_height)
+            .cloned()
+            .collect();
+        if fitting.is_empty() {
+            // Source is smaller than every rung: keep the lowest at source size
+            if let Some(lowest) = ladder.iter().min_by_key(|r| r.height) {
+                fitting.push(Rendition {
+                    height: source_height,
+                    ..lowest.clone()
+                });
+            }
+        }
+        fitting
+    }
+
+    /// Encoder arguments shared by HLS and DASH: one scaled, keyframe-aligned
+    /// video stream (plus audio) per rendition.
+    fn encode_args(&self, ladder: &[Rendition], has_audio: bool) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-i".into(), self.input.to_str().unwrap().into()];
+
+        let mut filter = format!("[0:v]split={}", ladder.len());
+        for i in 0..ladder.len() {
+            filter.push_str(&format!("[s{}]", i));
+        }
+        for (i, rendition) in ladder.iter().enumerate() {
+            filter.push_str(&format!(";[s{}]scale=-2:{}[v{}]", i, rendition.height, i));
+        }
+        args.extend(["-filter_complex".into(), filter]);
+
+        for (i, rendition) in ladder.iter().enumerate() {
+            args.extend(["-map".into(), format!("[v{}]", i)]);
+            args.extend([format!("-c:v:{}", i), "libx264".into()]);
+            args.extend([
+                format!("-b:v:{}", i),
+                format!("{}k", rendition.video_bitrate),
+            ]);
+            args.extend([
+                format!("-maxrate:v:{}", i),
+                format!("{}k", rendition.video_bitrate * 107 / 100),
+            ]);
+            args.extend([
+                format!("-bufsize:v:{}", i),
+                format!("{}k", rendition.video_bitrate * 3 / 2),
+            ]);
+        }
+        if has_audio {
+            for (i, rendition) in ladder.iter().enumerate() {
+                args.extend(["-map".into(), "0:a:0".into()]);
+                args.extend([format!("-c:a:{}", i), "aac".into()]);
+                args.extend([
+                    format!("-b:a:{}", i),
+                    format!("{}k", rendition.audio_bitrate),
+                ]);
+            }
+        }
+
+        // Keyframes exactly on segment boundaries so renditions switch cleanly
+        args.extend([
+            "-force_key_frames".into(),
+            format!("expr:gte(t,n_forced*{})", self.segment_duration),
+        ]);
+        args.extend(["-sc_threshold".into(), "0".into()]);
+        args.extend(["-pix_fmt".into(), "yuv420p".into()]);
+        args
+    }
+
+    fn package_hls(&self, ladder: &[Rendition], has_audio: bool) -> Result<()> {
+        let mut args = self.encode_args(ladder, has_audio);
+        let stream_map = (0..ladder.len())
+            .map(|i| {
+                if has_audio {
+                    format!("v:{},a:{}", i, i)
+                } else {
+                    format!("v:{}", i)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Flat layout: FFmpeg writes the master playlist next to the first variant
+        let segments = self.output.join("stream_%v_%05d.ts");
+        let playlists = self.output.join("stream_%v.m3u8");
+
+        args.extend(["-f".into(), "hls".into()]);
+        args.extend(["-hls_time".into(), self.segment_duration.to_string()]);
+        args.extend(["-hls_playlist_type".into(), "vod".into()]);
+        args.extend(["-hls_flags".into(), "independent_segments".into()]);
+        args.extend([
+            "-hls_segment_filename".into(),
+            segments.to_str().unwrap().into(),
+        ]);
+        args.extend(["-master_pl_name".into(), "master.m3u8".into()]);
+        args.extend(["-var_stream_map".into(), stream_map]);
+        args.extend(["-y".into(), playlists.to_str().unwrap().into()]);
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_ffmpeg_command(&args)
+    }
+
+    fn package_dash(&self, ladder: &[Rendition], has_audio: bool) -> Result<()> {
+        let mut args = self.encode_args(ladder, has_audio);
+        let adaptation_sets = if has_audio {
+            "id=0,streams=v id=1,streams=a"
+        } else {
+            "id=0,streams=v"
+        };
+        let manifest = self.output.join("manifest.mpd");
+
+        args.extend(["-f".into(), "dash".into()]);
+        args.extend(["-seg_duration".into(), self.segment_duration.to_string()]);
+        args.extend(["-use_template".into(), "1".into()]);
+        args.extend(["-use_timeline".into(), "1".into()]);
+        args.extend(["-adaptation_sets".into(), adaptation_sets.into()]);
+        args.extend(["-y".into(), manifest.to_str().unwrap().into()]);
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_ffmpeg_command(&args)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+        let ladder = self.ladder(info.dimensions().map(|(_, h)| h));
+        if ladder.is_empty() {
+            anyhow::bail!("No renditions to encode");
+        }
+        let has_audio = info.audio_stream().is_some();
+        fs::create_dir_all(&self.output)?;
+
+        if self.hls || !self.dash {
+            self.package_hls(&ladder, has_audio)?;
+            for (variant, playlist) in verify_hls(&self.output)? {
+                println!(
+                    "{}: {} kbit/s, {} segments, {:.1}s",
+                    variant.uri,
+                    variant.bandwidth / 1000,
+                    playlist.segments.len(),
+                    playlist.segments.iter().map(|(d, _)| d).sum::<f64>()
+                );
+            }
+        }
+
+        if self.dash {
+            self.package_dash(&ladder, has_audio)?;
+            if !self.output.join("manifest.mpd").exists() {
+                anyhow::bail!("DASH manifest was not written");
+            }
+            println!("manifest.mpd written");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, process::Command};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StreamInfo {
+    pub index: usize,
+    pub codec_type: Option<String>,
+    pub codec_name: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub r_frame_rate: Option<String>,
+    pub sample_rate: Option<String>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    #[serde(default)]
+    pub disposition: HashMap<String, u8>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormatInfo {
+    pub format_name: Option<String>,
+    pub duration: Option<String>,
+    pub size: Option<String>,
+    pub bit_rate: Option<String>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChapterInfo {
+    pub start_time: String,
+    pub end_time: String,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+/// Subset of `ffprobe -show_format -show_streams -show_chapters` output used
+/// across commands.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MediaInfo {
+    #[serde(default)]
+    pub streams: Vec<StreamInfo>,
+    pub format: FormatInfo,
+    #[serde(default)]
+    pub chapters: Vec<ChapterInfo>,
+}
+
+impl MediaInfo {
+    pub fn duration(&self) -> Option<f64> {
+        self.format.duration.as_ref()?.parse().ok()
+    }
+
+    pub fn video_stream(&self) -> Option<&StreamInfo> {
+        self.streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some("video"))
+    }
+
+    pub fn audio_stream(&self) -> Option<&StreamInfo> {
+        self.streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some("audio"))
+    }
+
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let video = self.video_stream()?;
+        Some((video.width?, video.height?))
+    }
+
+    pub fn frame_rate(&self) -> Option<f64> {
+        parse_rational(self.video_stream()?.r_frame_rate.as_deref()?)
+    }
+}
+
+/// Parse FFmpeg rationals such as `30000/1001` or plain numbers.
+pub fn parse_rational(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((num, den)) => {
+            let num: f64 = num.parse().ok()?;
+            let den: f64 = den.parse().ok()?;
+            if den == 0.0 {
+                None
+            } else {
+                Some(num / den)
+            }
+        }
+        None => value.parse().ok(),
+    }
+}
+
+pub fn probe(path: &Path) -> Result<MediaInfo> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_format",
+            "-show_streams",
+            "-show_chapters",
+            "-of",
+            "json",
+            path.to_str().unwrap(),
+        ])
+        .output()
+        .context("Failed to execute ffprobe command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("FFprobe error: {}", stderr);
+    }
+
+    serde_json::from_slice(&output.stdout).context("Failed to parse ffprobe output")
+}
@@ -0,0 +1,537 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::probe::probe;
+use crate::utils::{
+    escape_filter_arg, ext_by_filename, get_file_arg, parse_time, run_ffmpeg_command, FileType,
+};
+
+#[derive(Subcommand)]
+pub enum SubtitlesCommand {
+    /// Burn subtitles into the picture
+    Burn(BurnSubtitlesArgs),
+
+    /// Add subtitles as soft tracks
+    Mux(MuxSubtitlesArgs),
+
+    /// Extract an embedded subtitle track
+    Extract(ExtractSubtitlesArgs),
+
+    /// Convert between SRT, WebVTT and ASS, optionally retiming
+    Convert(ConvertSubtitlesArgs),
+}
+
+impl SubtitlesCommand {
+    pub fn execute(&self) -> Result<()> {
+        match self {
+            Self::Burn(cmd) => cmd.execute(),
+            Self::Mux(cmd) => cmd.execute(),
+            Self::Extract(cmd) => cmd.execute(),
+            Self::Convert(cmd) => cmd.execute(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+}
+
+impl FromStr for SubtitleFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "srt" => Ok(Self::Srt),
+            "vtt" | "webvtt" => Ok(Self::Vtt),
+            "ass" | "ssa" => Ok(Self::Ass),
+            _ => anyhow::bail!("Unsupported subtitle format: {}", s),
+        }
+    }
+}
+
+impl SubtitleFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        ext_by_filename(path.to_str().unwrap())
+            .context("Subtitle file has no extension")?
+            .parse()
+    }
+
+    /// FFmpeg encoder producing this format
+    pub fn codec(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "webvtt",
+            Self::Ass => "ass",
+        }
+    }
+}
+
+/// A single subtitle with times in milliseconds. Lines are separated by `\n`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cue {
+    pub start: u64,
+    pub end: u64,
+    pub text: String,
+}
+
+fn parse_timestamp(value: &str) -> Result<u64> {
+    let seconds = parse_time(&value.trim().replace(',', "."))?;
+    Ok((seconds * 1000.0).round() as u64)
+}
+
+fn format_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn parse_timing(line: &str) -> Result<(u64, u64)> {
+    let (start, rest) = line
+        .split_once("-->")
+        .with_context(|| format!("Invalid timing line: {}", line))?;
+    // WebVTT cue settings follow the end time
+    let end = rest.split_whitespace().next().unwrap_or("");
+    Ok((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+fn parse_blocks(content: &str, format: SubtitleFormat) -> Result<Vec<Cue>> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+
+    for block in content.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        let Some(timing) = lines.iter().position(|l| l.contains("-->")) else {
+            // Numbering-only blocks, WEBVTT header, NOTE and STYLE blocks
+            continue;
+        };
+        if format == SubtitleFormat::Vtt && lines[0].starts_with("NOTE") {
+            continue;
+        }
+
+        let (start, end) = parse_timing(lines[timing])?;
+        cues.push(Cue {
+            start,
+            end,
+            text: lines[timing + 1..].join("\n"),
+        });
+    }
+
+    Ok(cues)
+}
+
+fn parse_ass(content: &str) -> Result<Vec<Cue>> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut fields: Vec<String> = Vec::new();
+
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|f| f.trim().to_lowercase()).collect();
+        } else if let Some(dialogue) = line.strip_prefix("Dialogue:") {
+            if fields.is_empty() {
+                anyhow::bail!("ASS Dialogue line before Format line");
+            }
+            let values: Vec<&str> = dialogue.trim().splitn(fields.len(), ',').collect();
+            let field = |name: &str| -> Result<&str> {
+                fields
+                    .iter()
+                    .position(|f| f == name)
+                    .and_then(|i| values.get(i).copied())
+                    .with_context(|| format!("ASS Dialogue line is missing {}", name))
+            };
+
+            cues.push(Cue {
+                start: parse_timestamp(field("start")?)?,
+                end: parse_timestamp(field("end")?)?,
+                text: strip_ass_tags(field("text")?),
+            });
+        }
+    }
+
+    Ok(cues)
+}
+
+fn strip_ass_tags(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '{' => in_tag = true,
+            '}' => in_tag = false,
+            _ if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    result.replace("\\N", "\n").replace("\\n", "\n")
+}
+
+pub fn parse_subtitles(content: &str, format: SubtitleFormat) -> Result<Vec<Cue>> {
+    match format {
+        SubtitleFormat::Srt | SubtitleFormat::Vtt => parse_blocks(content, format),
+        SubtitleFormat::Ass => parse_ass(content),
+    }
+}
+
+pub fn write_subtitles(cues: &[Cue], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+
+    match format {
+        SubtitleFormat::Srt => {
+            for (i, cue) in cues.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    i + 1,
+                    format_timestamp(cue.start, ','),
+                    format_timestamp(cue.end, ','),
+                    cue.text
+                ));
+            }
+        }
+        SubtitleFormat::Vtt => {
+            out.push_str("WEBVTT\n\n");
+            for cue in cues {
+                out.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    format_timestamp(cue.start, '.'),
+                    format_timestamp(cue.end, '.'),
+                    cue.text
+                ));
+            }
+        }
+        SubtitleFormat::Ass => {
+            out.push_str(
+                "[Script Info]\nScriptType: v4.00+\nPlayResX: 384\nPlayResY: 288\n\n\
+                 [V4+ Styles]\n\
+                 Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+                 Style: Default,Arial,16,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,1,0,2,10,10,10,1\n\n\
+                 [Events]\n\
+                 Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+            );
+            for cue in cues {
+                let centis = |ms: u64| {
+                    format!(
+                        "{}:{:02}:{:02}.{:02}",
+                        ms / 3_600_000,
+                        ms / 60_000 % 60,
+                        ms / 1000 % 60,
+                        ms % 1000 / 10
+                    )
+                };
+                out.push_str(&format!(
+                    "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+                    centis(cue.start),
+                    centis(cue.end),
+                    cue.text.replace('\n', "\\N")
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+/// Shift all cues by `offset` seconds, dropping cues that end before zero.
+pub fn shift_cues(cues: &[Cue], offset: f64) -> Vec<Cue> {
+    let offset_ms = (offset * 1000.0).round() as i64;
+    cues.iter()
+        .filter_map(|cue| {
+            let end = cue.end as i64 + offset_ms;
+            if end <= 0 {
+                return None;
+            }
+            Some(Cue {
+                start: (cue.start as i64 + offset_ms).max(0) as u64,
+                end: end as u64,
+                text: cue.text.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Retime cues for a video played back `speed` times faster.
+pub fn scale_cues(cues: &[Cue], speed: f64) -> Vec<Cue> {
+    cues.iter()
+        .map(|cue| Cue {
+            start: (cue.start as f64 / speed).round() as u64,
+            end: (cue.end as f64 / speed).round() as u64,
+            text: cue.text.clone(),
+        })
+        .collect()
+}
+
+#[derive(Args)]
+pub struct BurnSubtitlesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Subtitle file (SRT, WebVTT or ASS)")]
+    pub subtitles: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long, help = "Font name")]
+    pub font_name: Option<String>,
+    #[arg(long, help = "Font size")]
+    pub font_size: Option<u32>,
+    #[arg(long, help = "Text colour as RRGGBB hex")]
+    pub color: Option<String>,
+    #[arg(long, help = "Outline colour as RRGGBB hex")]
+    pub outline_color: Option<String>,
+    #[arg(long, help = "Outline width")]
+    pub outline: Option<f32>,
+    #[arg(long, help = "Vertical margin from the bottom")]
+    pub margin: Option<u32>,
+}
+
+/// Convert `RRGGBB` (optionally prefixed with `#`) to ASS `&HBBGGRR` order.
+fn ass_color(hex: &str) -> Result<String> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid colour: {} (expected RRGGBB)", hex);
+    }
+    Ok(format!("&H{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_uppercase())
+}
+
+impl BurnSubtitlesArgs {
+    pub fn filter(&self) -> Result<String> {
+        let mut style = Vec::new();
+        if let Some(font_name) = &self.font_name {
+            style.push(format!("FontName={}", font_name));
+        }
+        if let Some(font_size) = self.font_size {
+            style.push(format!("FontSize={}", font_size));
+        }
+        if let Some(color) = &self.color {
+            style.push(format!("PrimaryColour={}", ass_color(color)?));
+        }
+        if let Some(color) = &self.outline_color {
+            style.push(format!("OutlineColour={}", ass_color(color)?));
+        }
+        if let Some(outline) = self.outline {
+            style.push(format!("Outline={}", outline));
+        }
+        if let Some(margin) = self.margin {
+            style.push(format!("MarginV={}", margin));
+        }
+
+        let mut filter = format!(
+            "subtitles=filename={}",
+            escape_filter_arg(self.subtitles.to_str().unwrap())
+        );
+        if !style.is_empty() {
+            filter.push_str(&format!(
+                ":force_style={}",
+                escape_filter_arg(&style.join(","))
+            ));
+        }
+        Ok(filter)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let filter = self.filter()?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ];
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct MuxSubtitlesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, required = true, help = "Subtitle files to add")]
+    pub subtitles: Vec<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "ISO 639-2 language for each subtitle file, in order (e.g. eng)"
+    )]
+    pub language: Vec<String>,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Subtitle codec for soft subtitles in the given container
+pub fn subtitle_codec_by_ext(ext: &str) -> &'static str {
+    match ext.to_lowercase().as_str() {
+        "mp4" | "m4v" | "mov" => "mov_text",
+        "webm" => "webvtt",
+        _ => "copy",
+    }
+}
+
+impl MuxSubtitlesArgs {
+    pub fn execute(&self) -> Result<()> {
+        // Existing subtitle tracks are kept, new ones are numbered after them
+        let existing = probe(&self.input)?
+            .streams
+            .iter()
+            .filter(|s| s.codec_type.as_deref() == Some("subtitle"))
+            .count();
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        let ext = ext_by_filename(output.to_str().unwrap()).unwrap_or_default();
+
+        let mut args: Vec<String> = vec!["-i".into(), self.input.to_str().unwrap().into()];
+        for subtitle in &self.subtitles {
+            args.push("-i".into());
+            args.push(subtitle.to_str().unwrap().into());
+        }
+
+        args.extend(["-map".into(), "0".into()]);
+        for i in 0..self.subtitles.len() {
+            args.push("-map".into());
+            args.push((i + 1).to_string());
+        }
+
+        args.extend(["-c".into(), "copy".into()]);
+        args.extend(["-c:s".into(), subtitle_codec_by_ext(&ext).into()]);
+        for (i, language) in self.language.iter().enumerate() {
+            args.push(format!("-metadata:s:s:{}", existing + i));
+            args.push(format!("language={}", language));
+        }
+
+        args.extend(["-y".into(), output.to_str().unwrap().into()]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct ExtractSubtitlesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Subtitle track index (0 = first subtitle track)"
+    )]
+    pub track: usize,
+    #[arg(long, help = "List subtitle tracks instead of extracting")]
+    pub list: bool,
+}
+
+impl ExtractSubtitlesArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+        let tracks: Vec<_> = info
+            .streams
+            .iter()
+            .filter(|s| s.codec_type.as_deref() == Some("subtitle"))
+            .collect();
+
+        if self.list {
+            for (i, track) in tracks.iter().enumerate() {
+                println!(
+                    "{}: {} ({})",
+                    i,
+                    track.codec_name.as_deref().unwrap_or("unknown"),
+                    track.tags.get("language").map_or("und", String::as_str)
+                );
+            }
+            return Ok(());
+        }
+
+        if self.track >= tracks.len() {
+            anyhow::bail!(
+                "Subtitle track {} not found ({} available)",
+                self.track,
+                tracks.len()
+            );
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Subtitle, &self.output)?;
+        let format = SubtitleFormat::from_path(&output)?;
+        let map = format!("0:s:{}", self.track);
+
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-map",
+            &map,
+            "-c:s",
+            format.codec(),
+            "-y",
+            output.to_str().unwrap(),
+        ];
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct ConvertSubtitlesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Shift all cues by this many seconds (negative is earlier)"
+    )]
+    pub offset: Option<f64>,
+    #[arg(long, help = "Retime for a video sped up by this factor (e.g. 1.25)")]
+    pub speed: Option<f64>,
+}
+
+impl ConvertSubtitlesArgs {
+    pub fn execute(&self) -> Result<()> {
+        let content = fs::read_to_string(&self.input)?;
+        let mut cues = parse_subtitles(&content, SubtitleFormat::from_path(&self.input)?)?;
+
+        if let Some(speed) = self.speed {
+            if speed <= 0.0 {
+                anyhow::bail!("Speed must be greater than 0");
+            }
+            cues = scale_cues(&cues, speed);
+        }
+        if let Some(offset) = self.offset {
+            cues = shift_cues(&cues, offset);
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Subtitle, &self.output)?;
+        let format = SubtitleFormat::from_path(&output)?;
+        fs::write(&output, write_subtitles(&cues, format))?;
+        Ok(())
+    }
+}
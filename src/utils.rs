@@ -8,16 +8,36 @@ use std::{
 };
 
 pub fn run_ffmpeg_command(args: &[&str]) -> Result<()> {
+    run_ffmpeg_command_with_log(args).map(|_| ())
+}
+
+/// Run FFmpeg and return its stderr log, for filters that report through it
+/// (cropdetect, silencedetect, ...).
+pub fn run_ffmpeg_command_with_log(args: &[&str]) -> Result<String> {
     let output = Command::new("ffmpeg")
         .args(args)
         .output()
         .context("Failed to execute ffmpeg command")?;
 
+    let stderr = String::from_utf8_lossy(&output.stderr);
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("FFmpeg error: {}", stderr);
     }
-    Ok(())
+    Ok(stderr.into_owned())
+}
+
+/// Whether the installed FFmpeg was built with the given filter.
+pub fn ffmpeg_has_filter(name: &str) -> bool {
+    let Ok(output) = Command::new("ffmpeg")
+        .args(["-hide_banner", "-filters"])
+        .output()
+    else {
+        return false;
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(name))
 }
 
 pub fn verify_magic_bytes(file_path: &Path, expected_magic: &[u8]) -> Result<bool, std::io::Error> {
@@ -57,6 +77,7 @@ pub enum FileType {
     Video,
     Image,
     Audio,
+    Subtitle,
 }
 
 const VIDEO_EXTENSIONS: [&str; 7] = ["mp4", "webm", "mkv", "avi", "mov", "mpeg", "mpegts"];
@@ -64,6 +85,7 @@ const IMAGE_EXTENSIONS: [&str; 10] = [
     "jpg", "png", "webp", "gif", "bmp", "jpeg", "tiff", "svg", "ico", "icns",
 ];
 const AUDIO_EXTENSIONS: [&str; 7] = ["mp3", "wav", "aac", "flac", "ogg", "opus", "m4a"];
+const SUBTITLE_EXTENSIONS: [&str; 4] = ["srt", "vtt", "ass", "ssa"];
 
 pub fn open_file_dialog(file_type: FileType) -> Option<PathBuf> {
     let mut dialog = FileDialog::new().set_title("Select a file");
@@ -72,6 +94,7 @@ pub fn open_file_dialog(file_type: FileType) -> Option<PathBuf> {
         FileType::Video => dialog = dialog.add_filter("Video Files", &VIDEO_EXTENSIONS),
         FileType::Image => dialog = dialog.add_filter("Image Files", &IMAGE_EXTENSIONS),
         FileType::Audio => dialog = dialog.add_filter("Audio Files", &AUDIO_EXTENSIONS),
+        FileType::Subtitle => dialog = dialog.add_filter("Subtitle Files", &SUBTITLE_EXTENSIONS),
     }
 
     dialog.show_save_single_file().unwrap_or(None)
@@ -91,6 +114,70 @@ pub fn file_type_from_extension(extension: &str) -> Result<FileType> {
             Ok(FileType::Image)
         }
         "mp3" | "wav" | "aac" | "flac" | "ogg" | "opus" | "m4a" => Ok(FileType::Audio),
+        "srt" | "vtt" | "ass" | "ssa" => Ok(FileType::Subtitle),
         _ => Err(anyhow::anyhow!("Unsupported file type: {}", extension)),
     }
 }
+
+/// Parse a time given in seconds (`90`, `1.5`) or `[HH:]MM:SS[.ms]` format.
+pub fn parse_time(value: &str) -> Result<f64> {
+    let mut seconds = 0.0;
+    for part in value.trim().split(':') {
+        let part: f64 = part
+            .parse()
+            .with_context(|| format!("Invalid time: {}", value))?;
+        seconds = seconds * 60.0 + part;
+    }
+    Ok(seconds)
+}
+
+/// Format a byte count for display, e.g. `12.3 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [(f64, &str); 3] = [(1e9, "GB"), (1e6, "MB"), (1e3, "KB")];
+    for (size, unit) in UNITS {
+        if bytes as f64 >= size {
+            return format!("{:.1} {}", bytes as f64 / size, unit);
+        }
+    }
+    format!("{} B", bytes)
+}
+
+/// Parse a `WxH` size such as `1920x1080`.
+pub fn parse_dimensions(s: &str) -> Result<(u32, u32)> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| anyhow::anyhow!("Invalid size: {} (expected WxH)", s))?;
+    Ok((w.trim().parse()?, h.trim().parse()?))
+}
+
+/// Format seconds as `HH:MM:SS`.
+pub fn format_time(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        total / 60 % 60,
+        total % 60
+    )
+}
+
+/// Escape a value for use as a filter option inside a filtergraph (both the
+/// option and the graph level), e.g. file paths or user text.
+pub fn escape_filter_arg(value: &str) -> String {
+    let mut option = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+
+    let mut graph = String::with_capacity(option.len());
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            graph.push('\\');
+        }
+        graph.push(c);
+    }
+    graph
+}
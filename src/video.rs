@@ -1,10 +1,28 @@
+use crate::audio::afade_filter;
+use crate::filters::{filters_to_ffmpeg, Filter};
+use crate::image::{
+    combine_images_horizontal, combine_images_vertical, reshape_circle, reshape_rounded,
+    reshape_square, Rect,
+};
+use crate::meta::{write_chapters, Chapter, ChapterFormat};
+use crate::packaging::PackageArgs;
+use crate::probe::{parse_rational, probe, MediaInfo, StreamInfo};
+use crate::subtitles::{subtitle_codec_by_ext, SubtitlesCommand};
 use crate::utils::{
-    audio_codec_by_ext, create_temp_file, ext_by_filename, get_file_arg, run_ffmpeg_command,
-    FileType,
+    audio_codec_by_ext, create_temp_file, escape_filter_arg, ext_by_filename, ffmpeg_has_filter,
+    format_time, get_file_arg, parse_dimensions, parse_time, run_ffmpeg_command,
+    run_ffmpeg_command_with_log, FileType,
 };
 use anyhow::Result;
 use clap::{Args, Subcommand};
-use std::{fs::File, io::Write, path::PathBuf};
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use tempfile::NamedTempFile;
 
 #[derive(Subcommand)]
@@ -26,6 +44,75 @@ pub enum VideoCommand {
 
     /// Combine video with another video or image
     Combine(CombineVideoArgs),
+
+    /// Crop, scale, pad, rotate and flip a video in one pass
+    Transform(TransformArgs),
+
+    /// Detect black borders and optionally crop them
+    Autocrop(AutocropArgs),
+
+    /// Reframe to another aspect ratio (e.g. vertical or square)
+    Reframe(ReframeArgs),
+
+    /// Overlay an image logo on a video
+    Watermark(WatermarkArgs),
+
+    /// Draw text, a timecode or frame numbers on a video
+    Text(TextArgs),
+
+    /// Burn, mux, extract and convert subtitles
+    Subtitles(SubtitlesArgs),
+
+    /// Extract frames as images
+    Frames(FramesArgs),
+
+    /// Pick a representative frame as a thumbnail
+    Thumbnail(ThumbnailArgs),
+
+    /// Create a contact sheet (grid of frames) for a video
+    ContactSheet(ContactSheetArgs),
+
+    /// Detect scene changes, optionally splitting or saving thumbnails
+    Scenes(ScenesArgs),
+
+    /// Check for black frames, freezes and silence
+    Qc(QcArgs),
+
+    /// Measure quality loss between two videos (PSNR, SSIM, VMAF)
+    Compare(CompareArgs),
+
+    /// Fade video and audio in and/or out
+    Fade(FadeVideoArgs),
+
+    /// Package for adaptive streaming (HLS/DASH)
+    Package(PackageArgs),
+
+    /// List, select, reorder and re-tag streams without re-encoding
+    Streams(StreamsArgs),
+
+    /// Stabilize shaky footage (vidstab, or deshake as a fallback)
+    Stabilize(StabilizeArgs),
+
+    /// Colour correction, curves, LUTs and image-style filters
+    Color(ColorArgs),
+
+    /// Reduce picture noise (hqdn3d or nlmeans)
+    Denoise(DenoiseArgs),
+
+    /// Sharpen the picture (unsharp mask)
+    Sharpen(SharpenArgs),
+
+    /// Light picture denoise plus audio noise reduction in one pass
+    Cleanup(CleanupArgs),
+
+    /// Blur or pixelate regions, optionally timed or tracked with keyframes
+    Redact(RedactArgs),
+
+    /// Speed up into a timelapse by dropping or blending frames
+    Timelapse(TimelapseArgs),
+
+    /// Convert the frame rate (drop/duplicate, blend or interpolate)
+    Fps(FpsArgs),
 }
 
 impl VideoCommand {
@@ -37,10 +124,39 @@ impl VideoCommand {
             Self::Cut(cmd) => cmd.execute(),
             Self::ReplaceAudio(cmd) => cmd.execute(),
             Self::Combine(cmd) => cmd.execute(),
+            Self::Transform(cmd) => cmd.execute(),
+            Self::Autocrop(cmd) => cmd.execute(),
+            Self::Reframe(cmd) => cmd.execute(),
+            Self::Watermark(cmd) => cmd.execute(),
+            Self::Text(cmd) => cmd.execute(),
+            Self::Subtitles(cmd) => cmd.command.execute(),
+            Self::Frames(cmd) => cmd.execute(),
+            Self::Thumbnail(cmd) => cmd.execute(),
+            Self::ContactSheet(cmd) => cmd.execute(),
+            Self::Scenes(cmd) => cmd.execute(),
+            Self::Qc(cmd) => cmd.execute(),
+            Self::Compare(cmd) => cmd.execute(),
+            Self::Fade(cmd) => cmd.execute(),
+            Self::Package(cmd) => cmd.execute(),
+            Self::Streams(cmd) => cmd.execute(),
+            Self::Stabilize(cmd) => cmd.execute(),
+            Self::Color(cmd) => cmd.execute(),
+            Self::Denoise(cmd) => cmd.execute(),
+            Self::Sharpen(cmd) => cmd.execute(),
+            Self::Cleanup(cmd) => cmd.execute(),
+            Self::Redact(cmd) => cmd.execute(),
+            Self::Timelapse(cmd) => cmd.execute(),
+            Self::Fps(cmd) => cmd.execute(),
         }
     }
 }
 
+#[derive(Args)]
+pub struct SubtitlesArgs {
+    #[command(subcommand)]
+    pub command: SubtitlesCommand,
+}
+
 #[derive(Args)]
 pub struct ExtractAudioArgs {
     #[arg(short, long)]
@@ -90,15 +206,17 @@ impl MuteVideoArgs {
         // Get output or prompt for one
         let output = get_file_arg(FileType::Video, &self.output)?;
 
-        let args = [
+        // Keep every stream except audio (extra video, subtitles, attachments)
+        let mut args = vec![
             "-i",
             self.input.to_str().unwrap(),
-            "-an", // Disable audio
-            "-c:v",
-            "copy",
-            "-y",
-            output.to_str().unwrap(),
+            "-map",
+            "0",
+            "-map",
+            "-0:a",
         ];
+        args.extend(copy_codec_args(&output));
+        args.extend_from_slice(&["-y", output.to_str().unwrap()]);
 
         run_ffmpeg_command(&args)
     }
@@ -219,6 +337,15 @@ pub struct ReplaceAudioArgs {
     audio: PathBuf,
     #[arg(short, long)]
     output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Add the audio as an extra track instead of replacing existing ones"
+    )]
+    add: bool,
+    #[arg(short, long, help = "Language tag for the new track (e.g. eng, spa)")]
+    language: Option<String>,
+    #[arg(short, long, help = "Title for the new track")]
+    title: Option<String>,
 }
 
 impl ReplaceAudioArgs {
@@ -233,30 +360,60 @@ impl ReplaceAudioArgs {
         // Get output or prompt for one
         let output = get_file_arg(FileType::Video, &self.output)?;
 
-        let mut args = vec![
-            "-i",
-            self.video.to_str().unwrap(),
-            "-i",
-            self.audio.to_str().unwrap(),
-            "-c:v",
-            "copy",
-            "-c:a",
-            codec,
-            "-map",
-            "0:v:0",
-            "-map",
-            "1:a:0",
-            "-shortest",
-            "-y",
-            output.to_str().unwrap(),
+        // The new track is numbered after the audio tracks that are kept
+        let track = if self.add {
+            probe(&self.video)?
+                .streams
+                .iter()
+                .filter(|s| s.codec_type.as_deref() == Some("audio"))
+                .count()
+        } else {
+            0
+        };
+
+        let mut args: Vec<String> = vec![
+            "-i".into(),
+            self.video.to_str().unwrap().into(),
+            "-i".into(),
+            self.audio.to_str().unwrap().into(),
+            "-map".into(),
+            "0".into(),
         ];
+        if !self.add {
+            args.extend(["-map".into(), "-0:a".into()]);
+        }
+        args.extend(["-map".into(), "1:a:0".into()]);
+        args.extend(copy_codec_args(&output).into_iter().map(String::from));
+        args.extend([format!("-c:a:{}", track), codec.into()]);
 
         if codec == "pcm_s16le" {
-            args.extend_from_slice(&["-ar", "44100", "-ac", "2"]);
+            args.extend([
+                format!("-ar:a:{}", track),
+                "44100".into(),
+                format!("-ac:a:{}", track),
+                "2".into(),
+            ]);
         } else {
-            args.extend_from_slice(&["-b:a", "192k"]);
+            args.extend([format!("-b:a:{}", track), "192k".into()]);
+        }
+        if let Some(language) = &self.language {
+            args.extend([
+                format!("-metadata:s:a:{}", track),
+                format!("language={}", language),
+            ]);
         }
+        if let Some(title) = &self.title {
+            args.extend([
+                format!("-metadata:s:a:{}", track),
+                format!("title={}", title),
+            ]);
+        }
+        if !self.add {
+            args.push("-shortest".into());
+        }
+        args.extend(["-y".into(), output.to_str().unwrap().into()]);
 
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
         run_ffmpeg_command(&args)
     }
 }
@@ -310,7 +467,7 @@ impl CombineVideoArgs {
                 if self.inputs.len() != 2 {
                     anyhow::bail!("Overlay mode requires exactly two input files.");
                 }
-                format!("[0:v][1:v]overlay=0:0[v]")
+                "[0:v][1:v]overlay=0:0[v]".to_string()
             }
             _ => anyhow::bail!("Invalid combine mode. Use horizontal, vertical, or overlay"),
         };
@@ -351,3 +508,3097 @@ impl CombineVideoArgs {
         run_ffmpeg_command(&args)
     }
 }
+
+/// Crop region, either in pixels or as a centered aspect ratio.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CropSpec {
+    /// `WxH` (centered) or `WxH+X+Y`
+    Pixels {
+        width: u32,
+        height: u32,
+        x: Option<u32>,
+        y: Option<u32>,
+    },
+    /// `W:H`, the largest centered region with that aspect ratio
+    Aspect { num: u32, den: u32 },
+    /// Fixed size with FFmpeg expressions for the position, evaluated per frame
+    Expression {
+        width: u32,
+        height: u32,
+        x: String,
+        y: String,
+    },
+}
+
+impl FromStr for CropSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.contains(':') {
+            let (num, den) = parse_aspect(s)?;
+            return Ok(Self::Aspect { num, den });
+        }
+
+        let mut parts = s.split('+');
+        let (width, height) = parse_dimensions(parts.next().unwrap_or(""))?;
+        let x = parts.next().map(str::parse).transpose()?;
+        let y = parts.next().map(str::parse).transpose()?;
+        if x.is_some() != y.is_some() || parts.next().is_some() {
+            anyhow::bail!("Invalid crop: {} (expected WxH, WxH+X+Y or W:H)", s);
+        }
+
+        Ok(Self::Pixels {
+            width,
+            height,
+            x,
+            y,
+        })
+    }
+}
+
+impl CropSpec {
+    pub fn to_filter(&self) -> String {
+        match self {
+            Self::Pixels {
+                width,
+                height,
+                x: Some(x),
+                y: Some(y),
+            } => format!("crop={}:{}:{}:{}", width, height, x, y),
+            Self::Pixels { width, height, .. } => format!("crop={}:{}", width, height),
+            Self::Aspect { num, den } => format!(
+                "crop='trunc(min(iw,ih*{num}/{den})/2)*2':'trunc(min(ih,iw*{den}/{num})/2)*2'",
+                num = num,
+                den = den
+            ),
+            Self::Expression {
+                width,
+                height,
+                x,
+                y,
+            } => format!("crop=w={}:h={}:x='{}':y='{}'", width, height, x, y),
+        }
+    }
+}
+
+/// Target size for scaling. A missing side is derived from the aspect ratio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScaleSpec {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl FromStr for ScaleSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let side = |v: &str| -> Result<Option<u32>> {
+            match v.trim() {
+                "" | "-1" => Ok(None),
+                v => Ok(Some(v.parse()?)),
+            }
+        };
+
+        let (width, height) = match s.split_once('x') {
+            Some((w, h)) => (side(w)?, side(h)?),
+            None => (side(s)?, None),
+        };
+        if width.is_none() && height.is_none() {
+            anyhow::bail!("Invalid scale: {} (expected WxH, W, Wx-1 or -1xH)", s);
+        }
+
+        Ok(Self { width, height })
+    }
+}
+
+impl ScaleSpec {
+    pub fn to_filter(&self) -> String {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => format!(
+                "scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+                w, h
+            ),
+            (Some(w), None) => format!("scale={}:-2", w),
+            (None, Some(h)) => format!("scale=-2:{}", h),
+            (None, None) => "null".to_string(),
+        }
+    }
+}
+
+/// Target canvas for padding / letterboxing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PadSpec {
+    pub width: u32,
+    pub height: u32,
+    /// FFmpeg colour name or hex, or `blur` for a blurred copy of the video
+    pub fill: String,
+}
+
+impl PadSpec {
+    fn is_blur(&self) -> bool {
+        self.fill == "blur"
+    }
+}
+
+/// Parse an aspect ratio such as `9:16`.
+pub fn parse_aspect(s: &str) -> Result<(u32, u32)> {
+    let (num, den) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("Invalid aspect ratio: {} (expected W:H)", s))?;
+    let num: u32 = num.trim().parse()?;
+    let den: u32 = den.trim().parse()?;
+    if num == 0 || den == 0 {
+        anyhow::bail!("Invalid aspect ratio: {}", s);
+    }
+    Ok((num, den))
+}
+
+/// Fade in/out durations in seconds. The fade-out start is computed from the
+/// media duration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fade {
+    pub fade_in: Option<f64>,
+    pub fade_out: Option<f64>,
+    /// `afade` curve for the audio (video fades are linear)
+    pub curve: String,
+}
+
+impl Fade {
+    pub fn video_filter(&self, duration: f64) -> Option<String> {
+        let mut filters = Vec::new();
+        if let Some(fade_in) = self.fade_in {
+            filters.push(format!("fade=t=in:st=0:d={}", fade_in));
+        }
+        if let Some(fade_out) = self.fade_out {
+            filters.push(format!(
+                "fade=t=out:st={}:d={}",
+                (duration - fade_out).max(0.0),
+                fade_out
+            ));
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+
+    pub fn audio_filter(&self, duration: f64) -> Option<String> {
+        afade_filter(self.fade_in, self.fade_out, &self.curve, duration)
+    }
+}
+
+/// Geometric operations applied to a video in a single pass, in the order
+/// crop, rotate, flip, scale, pad, with optional fades on top.
+#[derive(Clone, Debug, Default)]
+pub struct Transform {
+    pub crop: Option<CropSpec>,
+    pub rotate: Option<f32>,
+    pub hflip: bool,
+    pub vflip: bool,
+    pub scale: Option<ScaleSpec>,
+    pub pad: Option<PadSpec>,
+    pub fade: Option<Fade>,
+}
+
+impl Transform {
+    fn rotate_filter(degrees: f32) -> Option<String> {
+        let normalized = degrees.rem_euclid(360.0);
+        if normalized == 0.0 {
+            None
+        } else if normalized == 90.0 {
+            Some("transpose=clock".to_string())
+        } else if normalized == 180.0 {
+            Some("hflip,vflip".to_string())
+        } else if normalized == 270.0 {
+            Some("transpose=cclock".to_string())
+        } else {
+            let rad = degrees.to_radians();
+            Some(format!(
+                "rotate={}:ow=rotw({}):oh=roth({}):c=black",
+                rad, rad, rad
+            ))
+        }
+    }
+
+    /// Build the `-filter_complex` graph for this transform, reading from
+    /// `[0:v]` and writing to `[v]`.
+    pub fn filter_graph(&self) -> String {
+        let mut chain: Vec<String> = Vec::new();
+
+        if let Some(crop) = &self.crop {
+            chain.push(crop.to_filter());
+        }
+        if let Some(rotate) = self.rotate.and_then(Self::rotate_filter) {
+            chain.push(rotate);
+        }
+        if self.hflip {
+            chain.push("hflip".to_string());
+        }
+        if self.vflip {
+            chain.push("vflip".to_string());
+        }
+        if let Some(scale) = &self.scale {
+            chain.push(scale.to_filter());
+        }
+
+        match &self.pad {
+            Some(pad) if pad.is_blur() => {
+                let (w, h) = (pad.width, pad.height);
+                chain.push("split[fgsrc][bgsrc]".to_string());
+                format!(
+                    "[0:v]{};[bgsrc]scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},boxblur=20:5[bg];\
+                     [fgsrc]scale={w}:{h}:force_original_aspect_ratio=decrease[fg];\
+                     [bg][fg]overlay=(W-w)/2:(H-h)/2,setsar=1[v]",
+                    chain.join(","),
+                    w = w,
+                    h = h
+                )
+            }
+            Some(pad) => {
+                chain.push(format!(
+                    "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color={c},setsar=1",
+                    w = pad.width,
+                    h = pad.height,
+                    c = pad.fill
+                ));
+                format!("[0:v]{}[v]", chain.join(","))
+            }
+            None if chain.is_empty() => "[0:v]null[v]".to_string(),
+            None => format!("[0:v]{}[v]", chain.join(",")),
+        }
+    }
+}
+
+pub fn transform_video(input: &Path, output: &Path, transform: &Transform) -> Result<()> {
+    let mut filter = transform.filter_graph();
+    let mut video_label = "[v]";
+    let mut audio_filter = None;
+
+    if let Some(fade) = &transform.fade {
+        let duration = probe(input)?
+            .duration()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine video duration"))?;
+        if let Some(video_fade) = fade.video_filter(duration) {
+            filter.push_str(&format!(";[v]{}[faded]", video_fade));
+            video_label = "[faded]";
+        }
+        audio_filter = fade.audio_filter(duration);
+    }
+
+    let mut args = vec![
+        "-i",
+        input.to_str().unwrap(),
+        "-filter_complex",
+        &filter,
+        "-map",
+        video_label,
+        "-map",
+        "0:a?",
+    ];
+    match &audio_filter {
+        Some(audio_filter) => args.extend(["-af", audio_filter]),
+        None => args.extend(["-c:a", "copy"]),
+    }
+    args.extend(["-y", output.to_str().unwrap()]);
+
+    run_ffmpeg_command(&args)
+}
+
+#[derive(Args)]
+pub struct TransformArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long, help = "Crop as WxH (centered), WxH+X+Y, or aspect ratio W:H")]
+    pub crop: Option<CropSpec>,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Rotation in degrees (90, 180, 270 are lossless, others fill with black)"
+    )]
+    pub rotate: Option<f32>,
+    #[arg(long, help = "Flip horizontally")]
+    pub hflip: bool,
+    #[arg(long, help = "Flip vertically")]
+    pub vflip: bool,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Scale to fit WxH preserving aspect ratio, or W / Wx-1 / -1xH for one side"
+    )]
+    pub scale: Option<ScaleSpec>,
+    #[arg(long, help = "Pad (letterbox) to a WxH canvas")]
+    pub pad: Option<String>,
+    #[arg(
+        long,
+        default_value = "black",
+        help = "Padding fill: a colour name or hex, or 'blur' for a blurred background"
+    )]
+    pub pad_fill: String,
+    #[arg(long, help = "Fade in duration in seconds")]
+    pub fade_in: Option<f64>,
+    #[arg(long, help = "Fade out duration in seconds")]
+    pub fade_out: Option<f64>,
+    #[arg(long, default_value = "tri", help = "Audio fade curve (see afade)")]
+    pub fade_curve: String,
+}
+
+impl TransformArgs {
+    pub fn transform(&self) -> Result<Transform> {
+        let pad = match &self.pad {
+            Some(pad) => {
+                let (width, height) = parse_dimensions(pad)?;
+                Some(PadSpec {
+                    width,
+                    height,
+                    fill: self.pad_fill.clone(),
+                })
+            }
+            None => None,
+        };
+
+        let fade = (self.fade_in.is_some() || self.fade_out.is_some()).then(|| Fade {
+            fade_in: self.fade_in,
+            fade_out: self.fade_out,
+            curve: self.fade_curve.clone(),
+        });
+
+        Ok(Transform {
+            crop: self.crop.clone(),
+            rotate: self.rotate,
+            hflip: self.hflip,
+            vflip: self.vflip,
+            scale: self.scale.clone(),
+            pad,
+            fade,
+        })
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let transform = self.transform()?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        transform_video(&self.input, &output, &transform)
+    }
+}
+
+/// Extract every `crop=W:H:X:Y` suggestion printed by the `cropdetect` filter.
+pub fn parse_cropdetect(log: &str) -> Vec<(u32, u32, u32, u32)> {
+    log.lines()
+        .filter(|line| line.contains("cropdetect"))
+        .filter_map(|line| {
+            let crop = line.rsplit("crop=").next()?;
+            let values: Vec<u32> = crop
+                .trim()
+                .split(':')
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()
+                .ok()?;
+            match values.as_slice() {
+                [w, h, x, y] => Some((*w, *h, *x, *y)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Pick the crop rectangle suggested most often across samples. Ties go to
+/// the larger rectangle so fades to black don't eat into the picture.
+pub fn most_stable_crop(crops: &[(u32, u32, u32, u32)]) -> Option<(u32, u32, u32, u32)> {
+    let mut counts: Vec<((u32, u32, u32, u32), usize)> = Vec::new();
+    for crop in crops {
+        match counts.iter_mut().find(|(c, _)| c == crop) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*crop, 1)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|((w, h, _, _), count)| (*count, w * h))
+        .map(|(crop, _)| crop)
+}
+
+#[derive(Args)]
+pub struct AutocropArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Output file; when given the detected crop is applied"
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Apply the detected crop (prompts for output if not given)"
+    )]
+    pub apply: bool,
+    #[arg(long, default_value_t = 20, help = "Number of frames to sample")]
+    pub samples: u32,
+    #[arg(
+        long,
+        default_value_t = 24,
+        help = "Black threshold (0-255), higher treats brighter pixels as border"
+    )]
+    pub limit: u32,
+}
+
+impl AutocropArgs {
+    pub fn detect(&self) -> Result<CropSpec> {
+        let info = probe(&self.input)?;
+        let duration = info
+            .duration()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine video duration"))?;
+        let rate = self.samples.max(1) as f64 / duration.max(0.001);
+
+        let filter = format!(
+            "fps={},cropdetect=limit={}:round=2:reset=1",
+            rate, self.limit
+        );
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ];
+        let log = run_ffmpeg_command_with_log(&args)?;
+
+        let crops = parse_cropdetect(&log);
+        let (width, height, x, y) = most_stable_crop(&crops)
+            .ok_or_else(|| anyhow::anyhow!("cropdetect did not report any crop"))?;
+        let count = crops
+            .iter()
+            .filter(|c| **c == (width, height, x, y))
+            .count();
+
+        match info.dimensions() {
+            Some((w, h)) if (w, h) == (width, height) => {
+                println!("No black borders detected ({}x{})", w, h)
+            }
+            Some((w, h)) => println!(
+                "Detected crop: {}x{}+{}+{} from {}x{} ({} of {} samples)",
+                width,
+                height,
+                x,
+                y,
+                w,
+                h,
+                count,
+                crops.len()
+            ),
+            None => println!(
+                "Detected crop: {}x{}+{}+{} ({} of {} samples)",
+                width,
+                height,
+                x,
+                y,
+                count,
+                crops.len()
+            ),
+        }
+
+        Ok(CropSpec::Pixels {
+            width,
+            height,
+            x: Some(x),
+            y: Some(y),
+        })
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let crop = self.detect()?;
+        if !self.apply && self.output.is_none() {
+            return Ok(());
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        let transform = Transform {
+            crop: Some(crop),
+            ..Default::default()
+        };
+        transform_video(&self.input, &output, &transform)
+    }
+}
+
+/// Largest even-sized window with aspect `num:den` that fits inside `width`x`height`.
+pub fn aspect_window(width: u32, height: u32, num: u32, den: u32) -> (u32, u32) {
+    let (width, height, num, den) = (width as u64, height as u64, num as u64, den as u64);
+    let (w, h) = if width * den > height * num {
+        (height * num / den, height)
+    } else {
+        (width, width * den / num)
+    };
+    ((w as u32) & !1, (h as u32) & !1)
+}
+
+/// Smallest even-sized canvas with aspect `num:den` that contains `width`x`height`.
+pub fn aspect_canvas(width: u32, height: u32, num: u32, den: u32) -> (u32, u32) {
+    let (width, height, num, den) = (width as u64, height as u64, num as u64, den as u64);
+    let (w, h) = if width * den > height * num {
+        (width, (width * den).div_ceil(num))
+    } else {
+        ((height * num).div_ceil(den), height)
+    };
+    ((w as u32 + 1) & !1, (h as u32 + 1) & !1)
+}
+
+/// Normalized (0..1) centre of the change between consecutive frames. Frames
+/// without movement keep the previous centre.
+pub fn motion_centers(frames: &[GrayImage]) -> Vec<(f64, f64)> {
+    let mut centers = Vec::with_capacity(frames.len());
+    let mut last = (0.5, 0.5);
+
+    for pair in frames.windows(2) {
+        let (width, height) = pair[1].dimensions();
+        let (mut sum, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+
+        for (x, y, pixel) in pair[1].enumerate_pixels() {
+            let Some(prev) = pair[0].get_pixel_checked(x, y) else {
+                continue;
+            };
+            let diff = (pixel.0[0] as f64 - prev.0[0] as f64).abs();
+            // Ignore sensor noise and compression flicker
+            if diff > 8.0 {
+                sum += diff;
+                sum_x += diff * x as f64;
+                sum_y += diff * y as f64;
+            }
+        }
+
+        if sum > 0.0 {
+            last = (
+                (sum_x / sum + 0.5) / width as f64,
+                (sum_y / sum + 0.5) / height as f64,
+            );
+        }
+        centers.push(last);
+    }
+
+    // The first frame has nothing to compare against
+    if let Some(first) = centers.first().copied() {
+        centers.insert(0, first);
+    } else if !frames.is_empty() {
+        centers.push(last);
+    }
+    centers
+}
+
+/// Centered moving average over `window` samples.
+pub fn smooth_path(values: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(half);
+            let end = (i + half + 1).min(values.len());
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect()
+}
+
+/// Build a piecewise-linear FFmpeg expression of `t` through `(time, value)` keyframes.
+pub fn crop_path_expression(keyframes: &[(f64, f64)]) -> String {
+    let Some(&(_, last)) = keyframes.last() else {
+        return "0".to_string();
+    };
+
+    let mut expr = format!("{:.1}", last);
+    for pair in keyframes.windows(2).rev() {
+        let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+        if t1 <= t0 {
+            continue;
+        }
+        let slope = (v1 - v0) / (t1 - t0);
+        expr = format!(
+            "if(lt(t,{:.3}),({:.1})+({:.3})*(t-{:.3}),{})",
+            t1, v0, slope, t0, expr
+        );
+    }
+    expr
+}
+
+const MAX_PATH_KEYFRAMES: usize = 60;
+
+#[derive(Args)]
+pub struct ReframeArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(short, long, default_value = "9:16", help = "Target aspect ratio W:H")]
+    pub aspect: String,
+    #[arg(
+        short,
+        long,
+        default_value = "center",
+        help = "Reframe mode: center, blur, or motion"
+    )]
+    pub mode: String,
+    #[arg(long, help = "Final output size WxH (e.g. 1080x1920)")]
+    pub size: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "Frames per second analysed in motion mode"
+    )]
+    pub sample_rate: f64,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Samples averaged to smooth the motion path"
+    )]
+    pub smoothing: usize,
+}
+
+impl ReframeArgs {
+    fn motion_crop(&self, width: u32, height: u32, num: u32, den: u32) -> Result<CropSpec> {
+        let (crop_w, crop_h) = aspect_window(width, height, num, den);
+
+        // Analyse small grayscale frames; only the relative position matters
+        let temp_dir = tempfile::tempdir()?;
+        let pattern = temp_dir.path().join("frame_%05d.png");
+        let filter = format!("fps={},scale=160:-2,format=gray", self.sample_rate);
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-y",
+            pattern.to_str().unwrap(),
+        ];
+        run_ffmpeg_command(&args)?;
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(temp_dir.path())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        paths.sort();
+        let frames = paths
+            .iter()
+            .map(|path| Ok(image::open(path)?.to_luma8()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let centers = motion_centers(&frames);
+        let xs: Vec<f64> = centers.iter().map(|c| c.0).collect();
+        let ys: Vec<f64> = centers.iter().map(|c| c.1).collect();
+        let xs = smooth_path(&xs, self.smoothing);
+        let ys = smooth_path(&ys, self.smoothing);
+
+        let step = xs.len().div_ceil(MAX_PATH_KEYFRAMES).max(1);
+        let keyframes = |values: &[f64], size: u32, window: u32| -> Vec<(f64, f64)> {
+            let max = (size - window) as f64;
+            values
+                .iter()
+                .enumerate()
+                .step_by(step)
+                .map(|(i, v)| {
+                    let offset = (v * size as f64 - window as f64 / 2.0).clamp(0.0, max);
+                    (i as f64 / self.sample_rate, offset)
+                })
+                .collect()
+        };
+
+        Ok(CropSpec::Expression {
+            width: crop_w,
+            height: crop_h,
+            x: crop_path_expression(&keyframes(&xs, width, crop_w)),
+            y: crop_path_expression(&keyframes(&ys, height, crop_h)),
+        })
+    }
+
+    pub fn transform(&self) -> Result<Transform> {
+        let (num, den) = parse_aspect(&self.aspect)?;
+        let size = self.size.as_deref().map(parse_dimensions).transpose()?;
+        let scale = size.map(|(w, h)| ScaleSpec {
+            width: Some(w),
+            height: Some(h),
+        });
+
+        let transform = match self.mode.as_str() {
+            "center" => Transform {
+                crop: Some(CropSpec::Aspect { num, den }),
+                scale,
+                ..Default::default()
+            },
+            "blur" => {
+                let (width, height) = match size {
+                    Some(size) => size,
+                    None => {
+                        let (w, h) = probe(&self.input)?
+                            .dimensions()
+                            .ok_or_else(|| anyhow::anyhow!("Could not determine video size"))?;
+                        aspect_canvas(w, h, num, den)
+                    }
+                };
+                Transform {
+                    pad: Some(PadSpec {
+                        width,
+                        height,
+                        fill: "blur".to_string(),
+                    }),
+                    ..Default::default()
+                }
+            }
+            "motion" => {
+                let (w, h) = probe(&self.input)?
+                    .dimensions()
+                    .ok_or_else(|| anyhow::anyhow!("Could not determine video size"))?;
+                Transform {
+                    crop: Some(self.motion_crop(w, h, num, den)?),
+                    scale,
+                    ..Default::default()
+                }
+            }
+            _ => anyhow::bail!("Invalid reframe mode. Use center, blur, or motion"),
+        };
+
+        Ok(transform)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let transform = self.transform()?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        transform_video(&self.input, &output, &transform)
+    }
+}
+
+/// Placement of an overlay inside the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl FromStr for Anchor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "top-left" => Ok(Self::TopLeft),
+            "top" => Ok(Self::Top),
+            "top-right" => Ok(Self::TopRight),
+            "left" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            "right" => Ok(Self::Right),
+            "bottom-left" => Ok(Self::BottomLeft),
+            "bottom" => Ok(Self::Bottom),
+            "bottom-right" => Ok(Self::BottomRight),
+            _ => anyhow::bail!(
+                "Invalid position: {}. Use top-left, top, top-right, left, center, right, bottom-left, bottom, or bottom-right",
+                s
+            ),
+        }
+    }
+}
+
+impl Anchor {
+    /// X/Y expressions placing an `inner` box within an `outer` box, given the
+    /// variable names the filter uses for each (e.g. `W`/`w` for overlay).
+    pub fn expressions(
+        &self,
+        outer: (&str, &str),
+        inner: (&str, &str),
+        margin: u32,
+    ) -> (String, String) {
+        let (outer_w, outer_h) = outer;
+        let (inner_w, inner_h) = inner;
+
+        let x = match self {
+            Self::TopLeft | Self::Left | Self::BottomLeft => margin.to_string(),
+            Self::Top | Self::Center | Self::Bottom => format!("({}-{})/2", outer_w, inner_w),
+            Self::TopRight | Self::Right | Self::BottomRight => {
+                format!("{}-{}-{}", outer_w, inner_w, margin)
+            }
+        };
+        let y = match self {
+            Self::TopLeft | Self::Top | Self::TopRight => margin.to_string(),
+            Self::Left | Self::Center | Self::Right => format!("({}-{})/2", outer_h, inner_h),
+            Self::BottomLeft | Self::Bottom | Self::BottomRight => {
+                format!("{}-{}-{}", outer_h, inner_h, margin)
+            }
+        };
+        (x, y)
+    }
+}
+
+/// Timeline `enable` expression for a filter active between `start` and `end`.
+pub fn enable_expression(start: Option<f64>, end: Option<f64>) -> Option<String> {
+    match (start, end) {
+        (Some(start), Some(end)) => Some(format!("between(t,{},{})", start, end)),
+        (Some(start), None) => Some(format!("gte(t,{})", start)),
+        (None, Some(end)) => Some(format!("lte(t,{})", end)),
+        (None, None) => None,
+    }
+}
+
+#[derive(Args)]
+pub struct WatermarkArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short = 'l', long, help = "Logo image")]
+    pub logo: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value = "bottom-right",
+        help = "Position: top-left, top, top-right, left, center, right, bottom-left, bottom, bottom-right"
+    )]
+    pub position: Anchor,
+    #[arg(long, default_value_t = 20, help = "Margin from the edges in pixels")]
+    pub margin: u32,
+    #[arg(
+        long,
+        default_value_t = 0.15,
+        help = "Logo width relative to the video width (0 keeps the original size)"
+    )]
+    pub scale: f32,
+    #[arg(long, default_value_t = 1.0, help = "Logo opacity (0.0 - 1.0)")]
+    pub opacity: f32,
+    #[arg(short, long, help = "Show from this time (seconds or HH:MM:SS)")]
+    pub start: Option<String>,
+    #[arg(short, long, help = "Show until this time (seconds or HH:MM:SS)")]
+    pub end: Option<String>,
+}
+
+impl WatermarkArgs {
+    pub fn filter_graph(&self, video_width: Option<u32>) -> Result<String> {
+        let mut logo = vec!["format=rgba".to_string()];
+        if self.scale > 0.0 {
+            let width =
+                video_width.ok_or_else(|| anyhow::anyhow!("Could not determine video size"))?;
+            let logo_width = ((width as f32 * self.scale) as u32).max(2) & !1;
+            logo.push(format!("scale={}:-2", logo_width));
+        }
+        if self.opacity < 1.0 {
+            logo.push(format!(
+                "colorchannelmixer=aa={}",
+                self.opacity.clamp(0.0, 1.0)
+            ));
+        }
+
+        let (x, y) = self
+            .position
+            .expressions(("W", "H"), ("w", "h"), self.margin);
+        let mut overlay = format!("overlay={}:{}", x, y);
+        let start = self.start.as_deref().map(parse_time).transpose()?;
+        let end = self.end.as_deref().map(parse_time).transpose()?;
+        if let Some(enable) = enable_expression(start, end) {
+            overlay.push_str(&format!(":enable='{}'", enable));
+        }
+
+        Ok(format!(
+            "[1:v]{}[logo];[0:v][logo]{}[v]",
+            logo.join(","),
+            overlay
+        ))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let video_width = if self.scale > 0.0 {
+            probe(&self.input)?.dimensions().map(|(w, _)| w)
+        } else {
+            None
+        };
+        let filter = self.filter_graph(video_width)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-i",
+            self.logo.to_str().unwrap(),
+            "-filter_complex",
+            &filter,
+            "-map",
+            "[v]",
+            "-map",
+            "0:a?",
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ];
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+#[derive(Args)]
+pub struct TextArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(short, long, help = "Text to draw")]
+    pub text: Option<String>,
+    #[arg(long, help = "Burn in the SMPTE timecode")]
+    pub timecode: bool,
+    #[arg(long, help = "Burn in the frame number")]
+    pub frame_number: bool,
+    #[arg(long, help = "Font file (TTF/OTF)")]
+    pub font_file: Option<PathBuf>,
+    #[arg(long, default_value_t = 48, help = "Font size in pixels")]
+    pub size: u32,
+    #[arg(
+        long,
+        default_value = "white",
+        help = "Text colour (name, hex, or name@alpha)"
+    )]
+    pub color: String,
+    #[arg(
+        long,
+        help = "Draw a box behind the text with this colour (e.g. black@0.5)"
+    )]
+    pub box_color: Option<String>,
+    #[arg(
+        short,
+        long,
+        default_value = "bottom",
+        help = "Position: top-left, top, top-right, left, center, right, bottom-left, bottom, bottom-right"
+    )]
+    pub position: Anchor,
+    #[arg(long, default_value_t = 20, help = "Margin from the edges in pixels")]
+    pub margin: u32,
+    #[arg(short, long, help = "Show from this time (seconds or HH:MM:SS)")]
+    pub start: Option<String>,
+    #[arg(short, long, help = "Show until this time (seconds or HH:MM:SS)")]
+    pub end: Option<String>,
+}
+
+impl TextArgs {
+    pub fn filter(&self, frame_rate: Option<&str>) -> Result<String> {
+        let mut options = Vec::new();
+
+        if let Some(font_file) = &self.font_file {
+            options.push(format!(
+                "fontfile={}",
+                escape_filter_arg(font_file.to_str().unwrap())
+            ));
+        }
+
+        match (&self.text, self.timecode, self.frame_number) {
+            (Some(text), false, false) => {
+                options.push(format!("text={}", escape_filter_arg(text)));
+                options.push("expansion=none".to_string());
+            }
+            (None, true, false) => {
+                let rate =
+                    frame_rate.ok_or_else(|| anyhow::anyhow!("Could not determine frame rate"))?;
+                options.push(format!("timecode={}", escape_filter_arg("00:00:00:00")));
+                options.push(format!("timecode_rate={}", rate));
+            }
+            (None, false, true) => options.push("text=%{n}".to_string()),
+            _ => anyhow::bail!("Specify exactly one of --text, --timecode or --frame-number"),
+        }
+
+        options.push(format!("fontsize={}", self.size));
+        options.push(format!("fontcolor={}", self.color));
+        if let Some(box_color) = &self.box_color {
+            options.push("box=1".to_string());
+            options.push(format!("boxcolor={}", box_color));
+            options.push(format!("boxborderw={}", self.size / 4));
+        }
+
+        let (x, y) = self
+            .position
+            .expressions(("w", "h"), ("tw", "th"), self.margin);
+        options.push(format!("x={}", x));
+        options.push(format!("y={}", y));
+
+        let start = self.start.as_deref().map(parse_time).transpose()?;
+        let end = self.end.as_deref().map(parse_time).transpose()?;
+        if let Some(enable) = enable_expression(start, end) {
+            options.push(format!("enable='{}'", enable));
+        }
+
+        Ok(format!("drawtext={}", options.join(":")))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let frame_rate = if self.timecode {
+            probe(&self.input)?
+                .video_stream()
+                .and_then(|s| s.r_frame_rate.clone())
+        } else {
+            None
+        };
+        let filter = self.filter(frame_rate.as_deref())?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ];
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+/// Which frames to extract from a video.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameSelection {
+    /// A single frame at this time in seconds
+    At(f64),
+    /// One frame every N seconds
+    Every(f64),
+    /// Every keyframe
+    Keyframes,
+    /// N frames evenly spaced over the whole video
+    Count(u32),
+}
+
+/// Extract frames into `dir` named after `template` (an FFmpeg pattern such
+/// as `frame_%04d.png`) and return the written files in order. `filter` is
+/// an optional video filter applied to each extracted frame. Files matching
+/// `template` from an earlier run are replaced.
+pub fn extract_frames(
+    input: &Path,
+    dir: &Path,
+    selection: &FrameSelection,
+    template: &str,
+    filter: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    // Leftovers from an earlier run would be returned as if this run wrote them
+    for stale in list_template_files(dir, template)? {
+        std::fs::remove_file(stale)?;
+    }
+    let pattern = dir.join(template);
+    let pattern = pattern.to_str().unwrap();
+
+    let mut args: Vec<String> = Vec::new();
+    let mut filters: Vec<String> = Vec::new();
+    match selection {
+        FrameSelection::At(time) => {
+            args.extend(["-ss".into(), time.to_string()]);
+            args.extend(["-i".into(), input.to_str().unwrap().into()]);
+            args.extend(["-frames:v".into(), "1".into()]);
+        }
+        FrameSelection::Every(seconds) => {
+            args.extend(["-i".into(), input.to_str().unwrap().into()]);
+            filters.push(format!("fps=1/{}", seconds));
+        }
+        FrameSelection::Keyframes => {
+            args.extend(["-skip_frame".into(), "nokey".into()]);
+            args.extend(["-i".into(), input.to_str().unwrap().into()]);
+            args.extend(["-fps_mode".into(), "vfr".into()]);
+        }
+        FrameSelection::Count(count) => {
+            let duration = probe(input)?
+                .duration()
+                .ok_or_else(|| anyhow::anyhow!("Could not determine video duration"))?;
+            // Sample the middle of each of `count` equal segments
+            let interval = duration / (*count).max(1) as f64;
+            args.extend(["-ss".into(), (interval / 2.0).to_string()]);
+            args.extend(["-i".into(), input.to_str().unwrap().into()]);
+            args.extend(["-frames:v".into(), count.to_string()]);
+            filters.push(format!("fps=1/{}", interval));
+        }
+    }
+    if let Some(filter) = filter {
+        filters.push(filter.to_string());
+    }
+    if !filters.is_empty() {
+        args.extend(["-vf".into(), filters.join(",")]);
+    }
+    args.extend(["-y".into(), pattern.into()]);
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_ffmpeg_command(&args)?;
+
+    list_template_files(dir, template)
+}
+
+/// Whether `name` is a file produced by the FFmpeg pattern `template`
+/// (`frame_%04d.png` matches `frame_0001.png` but not `frame_notes.png`).
+pub fn matches_template(template: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = template.split_once('%') else {
+        return name == template;
+    };
+    let Some((_, suffix)) = rest.split_once('d') else {
+        return false;
+    };
+    name.strip_prefix(prefix)
+        .and_then(|n| n.strip_suffix(suffix))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn list_template_files(dir: &Path, template: &str) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| matches_template(template, name))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Args)]
+pub struct FramesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Output directory")]
+    pub output: PathBuf,
+    #[arg(
+        long,
+        help = "Extract a single frame at this time (seconds or HH:MM:SS)"
+    )]
+    pub at: Option<String>,
+    #[arg(long, help = "Extract one frame every N seconds")]
+    pub every: Option<f64>,
+    #[arg(long, help = "Extract every keyframe")]
+    pub keyframes: bool,
+    #[arg(long, help = "Extract N evenly spaced frames")]
+    pub count: Option<u32>,
+    #[arg(
+        long,
+        default_value = "frame_%04d.png",
+        help = "File name template; %04d is replaced by the frame number"
+    )]
+    pub template: String,
+}
+
+impl FramesArgs {
+    pub fn selection(&self) -> Result<FrameSelection> {
+        let selection = match (&self.at, self.every, self.keyframes, self.count) {
+            (Some(at), None, false, None) => FrameSelection::At(parse_time(at)?),
+            (None, Some(every), false, None) if every > 0.0 => FrameSelection::Every(every),
+            (None, None, true, None) => FrameSelection::Keyframes,
+            (None, None, false, Some(count)) if count > 0 => FrameSelection::Count(count),
+            _ => anyhow::bail!("Specify exactly one of --at, --every, --keyframes or --count"),
+        };
+        Ok(selection)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let frames = extract_frames(
+            &self.input,
+            &self.output,
+            &self.selection()?,
+            &self.template,
+            None,
+        )?;
+        println!(
+            "Extracted {} frames to {}",
+            frames.len(),
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
+/// Mean luma (0-255) of a frame.
+pub fn frame_brightness(img: &GrayImage) -> f64 {
+    let total: u64 = img.pixels().map(|p| p.0[0] as u64).sum();
+    total as f64 / (img.width() as u64 * img.height() as u64).max(1) as f64
+}
+
+/// Variance of the Laplacian, a cheap focus measure: blurry frames score low.
+pub fn frame_sharpness(img: &GrayImage) -> f64 {
+    let (width, height) = img.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let luma = |x: u32, y: u32| img.get_pixel(x, y).0[0] as f64;
+    let mut values = Vec::with_capacity(((width - 2) * (height - 2)) as usize);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            values.push(
+                luma(x - 1, y) + luma(x + 1, y) + luma(x, y - 1) + luma(x, y + 1)
+                    - 4.0 * luma(x, y),
+            );
+        }
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+/// Index of the sharpest frame that is neither nearly black nor washed out.
+pub fn best_thumbnail(frames: &[GrayImage]) -> Option<usize> {
+    let scored: Vec<(usize, f64, f64)> = frames
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (i, frame_brightness(f), frame_sharpness(f)))
+        .collect();
+
+    let sharpest = |candidates: Vec<&(usize, f64, f64)>| {
+        candidates
+            .into_iter()
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|c| c.0)
+    };
+
+    sharpest(
+        scored
+            .iter()
+            .filter(|(_, brightness, _)| (16.0..=240.0).contains(brightness))
+            .collect(),
+    )
+    .or_else(|| sharpest(scored.iter().collect()))
+}
+
+/// Frames the `thumbnail` filter may buffer per candidate; longer videos
+/// are sampled down with `fps` first.
+pub const MAX_THUMBNAIL_BATCH: u32 = 300;
+
+/// `thumbnail` filter yielding about `candidates` frames spread over the
+/// whole video.
+pub fn thumbnail_filter(duration: Option<f64>, frame_rate: Option<f64>, candidates: u32) -> String {
+    let candidates = candidates.max(1);
+    let (Some(duration), Some(fps)) = (duration, frame_rate) else {
+        return "thumbnail=n=100".to_string();
+    };
+    let batch = ((duration * fps) as u32 / candidates).max(1);
+    if batch <= MAX_THUMBNAIL_BATCH {
+        return format!("thumbnail=n={}", batch);
+    }
+    let rate = (MAX_THUMBNAIL_BATCH * candidates) as f64 / duration;
+    format!("fps={:.4},thumbnail=n={}", rate, MAX_THUMBNAIL_BATCH)
+}
+
+#[derive(Args)]
+pub struct ThumbnailArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "Number of representative candidates to compare"
+    )]
+    pub candidates: u32,
+    #[arg(long, help = "Resize the thumbnail to this width")]
+    pub width: Option<u32>,
+    #[arg(long, help = "Shape type: circle, square, rounded")]
+    pub shape: Option<String>,
+    #[arg(long, help = "Border radius for rounded shape")]
+    pub radius: Option<u32>,
+}
+
+impl ThumbnailArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+
+        // FFmpeg's thumbnail filter picks the most representative frame of
+        // each batch; we then skip dark and blurry candidates.
+        let temp_dir = tempfile::tempdir()?;
+        let pattern = temp_dir.path().join("candidate_%03d.png");
+        let filter = thumbnail_filter(info.duration(), info.frame_rate(), self.candidates);
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-fps_mode",
+            "vfr",
+            "-y",
+            pattern.to_str().unwrap(),
+        ];
+        run_ffmpeg_command(&args)?;
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(temp_dir.path())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        paths.sort();
+        let candidates = paths
+            .iter()
+            .map(|path| Ok(image::open(path)?))
+            .collect::<Result<Vec<_>>>()?;
+        let luma: Vec<GrayImage> = candidates.iter().map(|img| img.to_luma8()).collect();
+        let best =
+            best_thumbnail(&luma).ok_or_else(|| anyhow::anyhow!("No frames could be extracted"))?;
+
+        let mut thumbnail = candidates[best].clone();
+        if let Some(width) = self.width {
+            let height = thumbnail.height() * width / thumbnail.width().max(1);
+            thumbnail = thumbnail.resize_exact(width, height, FilterType::Lanczos3);
+        }
+        thumbnail = match self.shape.as_deref() {
+            None => thumbnail,
+            Some("circle") => reshape_circle(&thumbnail),
+            Some("square") => reshape_square(&thumbnail),
+            Some("rounded") => reshape_rounded(&thumbnail, self.radius.unwrap_or(30)),
+            Some(_) => anyhow::bail!("Invalid shape type."),
+        };
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Image, &self.output)?;
+        thumbnail.save(&output)?;
+        Ok(())
+    }
+}
+
+/// Two header lines describing a video: its name, then duration, resolution
+/// and codecs.
+pub fn media_summary(name: &str, info: &MediaInfo) -> Vec<String> {
+    let mut details = Vec::new();
+    if let Some(duration) = info.duration() {
+        details.push(format!("Duration {}", format_time(duration)));
+    }
+    if let Some((width, height)) = info.dimensions() {
+        details.push(format!("{}x{}", width, height));
+    }
+    let codecs: Vec<&str> = [info.video_stream(), info.audio_stream()]
+        .iter()
+        .flatten()
+        .filter_map(|s| s.codec_name.as_deref())
+        .collect();
+    if !codecs.is_empty() {
+        details.push(codecs.join(" / "));
+    }
+
+    vec![name.to_string(), details.join("  |  ")]
+}
+
+#[derive(Args)]
+pub struct ContactSheetArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long, default_value_t = 4, help = "Number of columns")]
+    pub cols: u32,
+    #[arg(long, default_value_t = 5, help = "Number of rows")]
+    pub rows: u32,
+    #[arg(long, default_value_t = 320, help = "Width of each tile in pixels")]
+    pub tile_width: u32,
+}
+
+impl ContactSheetArgs {
+    pub fn execute(&self) -> Result<()> {
+        if self.cols == 0 || self.rows == 0 {
+            anyhow::bail!("Columns and rows must be greater than 0");
+        }
+
+        let info = probe(&self.input)?;
+        let duration = info
+            .duration()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine video duration"))?;
+        let count = self.cols * self.rows;
+        let interval = duration / count as f64;
+        let font_size = (self.tile_width / 16).max(10);
+        let temp_dir = tempfile::tempdir()?;
+
+        // Grab the middle of each segment, stamped with its timestamp
+        let mut tiles = Vec::new();
+        for i in 0..count {
+            let time = interval * (i as f64 + 0.5);
+            let filter = format!(
+                "scale={}:-2,drawtext=text={}:expansion=none:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4:x=w-tw-6:y=h-th-6",
+                self.tile_width & !1,
+                escape_filter_arg(&format_time(time)),
+                font_size
+            );
+            let frames = extract_frames(
+                &self.input,
+                temp_dir.path(),
+                &FrameSelection::At(time),
+                &format!("tile_{:04}_%d.png", i),
+                Some(&filter),
+            )?;
+            for frame in frames {
+                tiles.push(image::open(frame)?);
+            }
+        }
+        if tiles.is_empty() {
+            anyhow::bail!("No frames could be extracted");
+        }
+
+        let rows: Vec<DynamicImage> = tiles
+            .chunks(self.cols as usize)
+            .map(combine_images_horizontal)
+            .collect();
+        let grid = combine_images_vertical(&rows);
+
+        // Header with file name and probe data
+        let name = self
+            .input
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let lines = media_summary(name, &info);
+        let header_size = (grid.width() / 50).max(14);
+        let header_height = header_size * 3 + 20;
+        let header_path = temp_dir.path().join("header.png");
+        let color = format!("color=c=0x202020:s={}x{}", grid.width() & !1, header_height);
+        let header_filter = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                format!(
+                    "drawtext=text={}:expansion=none:fontsize={}:fontcolor=white:x=10:y={}",
+                    escape_filter_arg(line),
+                    header_size,
+                    10 + i as u32 * header_size * 3 / 2
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let args = [
+            "-f",
+            "lavfi",
+            "-i",
+            &color,
+            "-vf",
+            &header_filter,
+            "-frames:v",
+            "1",
+            "-y",
+            header_path.to_str().unwrap(),
+        ];
+        run_ffmpeg_command(&args)?;
+        let header = image::open(&header_path)?;
+
+        let sheet = combine_images_vertical(&[header, grid]);
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Image, &self.output)?;
+        sheet.save(&output)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Scene {
+    pub index: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Collect `pts_time` values printed by the `showinfo` filter.
+pub fn parse_showinfo_times(log: &str) -> Vec<f64> {
+    log.lines()
+        .filter(|line| line.contains("showinfo"))
+        .filter_map(|line| {
+            let value = line.split("pts_time:").nth(1)?;
+            value.split_whitespace().next()?.parse().ok()
+        })
+        .collect()
+}
+
+/// Turn scene cut times into scenes covering `0..duration`, ignoring cuts
+/// closer than `min_duration` seconds to the previous one.
+pub fn scenes_from_cuts(cuts: &[f64], duration: f64, min_duration: f64) -> Vec<Scene> {
+    let mut boundaries = vec![0.0];
+    for &cut in cuts {
+        if cut - boundaries[boundaries.len() - 1] >= min_duration && duration - cut >= min_duration
+        {
+            boundaries.push(cut);
+        }
+    }
+    boundaries.push(duration);
+
+    boundaries
+        .windows(2)
+        .enumerate()
+        .map(|(index, pair)| Scene {
+            index: index + 1,
+            start: pair[0],
+            end: pair[1],
+        })
+        .collect()
+}
+
+pub fn format_scenes(scenes: &[Scene], format: &str) -> Result<String> {
+    let report = match format {
+        "json" => serde_json::to_string_pretty(scenes)? + "\n",
+        "csv" => {
+            let mut csv = "index,start,end,duration\n".to_string();
+            for scene in scenes {
+                csv.push_str(&format!(
+                    "{},{:.3},{:.3},{:.3}\n",
+                    scene.index,
+                    scene.start,
+                    scene.end,
+                    scene.end - scene.start
+                ));
+            }
+            csv
+        }
+        "chapters" => {
+            let chapters: Vec<Chapter> = scenes
+                .iter()
+                .map(|scene| Chapter {
+                    start: scene.start,
+                    end: scene.end,
+                    title: format!("Scene {}", scene.index),
+                })
+                .collect();
+            write_chapters(&chapters, ChapterFormat::Ffmetadata)?
+        }
+        _ => anyhow::bail!("Invalid format. Use json, csv, or chapters"),
+    };
+    Ok(report)
+}
+
+#[derive(Args)]
+pub struct ScenesArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Write the report to a file instead of stdout")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value_t = 0.3,
+        help = "Scene change threshold (0.0 - 1.0), lower finds more cuts"
+    )]
+    pub threshold: f64,
+    #[arg(long, default_value_t = 1.0, help = "Minimum scene length in seconds")]
+    pub min_duration: f64,
+    #[arg(
+        short,
+        long,
+        default_value = "json",
+        help = "Report format: json, csv, or chapters"
+    )]
+    pub format: String,
+    #[arg(
+        long,
+        help = "Split the video into one file per scene in this directory"
+    )]
+    pub split: Option<PathBuf>,
+    #[arg(long, help = "Save one thumbnail per scene in this directory")]
+    pub thumbnails: Option<PathBuf>,
+}
+
+impl ScenesArgs {
+    pub fn detect(&self) -> Result<Vec<Scene>> {
+        let duration = probe(&self.input)?
+            .duration()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine video duration"))?;
+
+        let filter = format!("select='gt(scene,{})',showinfo", self.threshold);
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ];
+        let log = run_ffmpeg_command_with_log(&args)?;
+
+        Ok(scenes_from_cuts(
+            &parse_showinfo_times(&log),
+            duration,
+            self.min_duration,
+        ))
+    }
+
+    fn split(&self, scenes: &[Scene], dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let extension =
+            ext_by_filename(self.input.to_str().unwrap()).unwrap_or_else(|| "mp4".to_string());
+        let pattern = dir.join(format!("scene_%03d.{}", extension));
+        let times = scenes
+            .iter()
+            .skip(1)
+            .map(|scene| scene.start.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        if times.is_empty() {
+            let output = dir.join(format!("scene_001.{}", extension));
+            let args = [
+                "-i",
+                self.input.to_str().unwrap(),
+                "-c",
+                "copy",
+                "-y",
+                output.to_str().unwrap(),
+            ];
+            return run_ffmpeg_command(&args);
+        }
+
+        // Re-encode with keyframes at the cuts so segments start exactly there
+        let args = [
+            "-i",
+            self.input.to_str().unwrap(),
+            "-map",
+            "0:v",
+            "-map",
+            "0:a?",
+            "-force_key_frames",
+            &times,
+            "-f",
+            "segment",
+            "-segment_times",
+            &times,
+            // Number like the 1-based scene indexes used for thumbnails
+            "-segment_start_number",
+            "1",
+            "-reset_timestamps",
+            "1",
+            "-y",
+            pattern.to_str().unwrap(),
+        ];
+        run_ffmpeg_command(&args)
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let scenes = self.detect()?;
+        let report = format_scenes(&scenes, &self.format)?;
+
+        match &self.output {
+            Some(output) => std::fs::write(output, report)?,
+            None => print!("{}", report),
+        }
+
+        if let Some(dir) = &self.split {
+            self.split(&scenes, dir)?;
+        }
+
+        if let Some(dir) = &self.thumbnails {
+            for scene in &scenes {
+                let time = scene.start + ((scene.end - scene.start) / 2.0).min(0.5);
+                extract_frames(
+                    &self.input,
+                    dir,
+                    &FrameSelection::At(time),
+                    &format!("scene_{:03}_%d.jpg", scene.index),
+                    None,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct QcInterval {
+    /// `black`, `freeze` or `silence`
+    pub kind: String,
+    pub start: f64,
+    pub end: f64,
+    pub duration: f64,
+}
+
+fn log_value(line: &str, key: &str) -> Option<f64> {
+    let value = line.split(key).nth(1)?;
+    value
+        .trim_start_matches(':')
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Parse `blackdetect`, `freezedetect` and `silencedetect` output into
+/// intervals. Freezes and silences still open at the end last until `duration`.
+pub fn parse_qc_log(log: &str, duration: f64) -> Vec<QcInterval> {
+    let mut intervals = Vec::new();
+    let mut freeze_start = None;
+    let mut silence_start = None;
+
+    let mut push = |kind: &str, start: f64, end: f64| {
+        intervals.push(QcInterval {
+            kind: kind.to_string(),
+            start,
+            end,
+            duration: end - start,
+        })
+    };
+
+    for line in log.lines() {
+        if let (Some(start), Some(end)) =
+            (log_value(line, "black_start"), log_value(line, "black_end"))
+        {
+            push("black", start, end);
+        } else if let Some(start) = log_value(line, "freeze_start") {
+            freeze_start = Some(start);
+        } else if let Some(end) = log_value(line, "freeze_end") {
+            if let Some(start) = freeze_start.take() {
+                push("freeze", start, end);
+            }
+        } else if let Some(start) = log_value(line, "silence_start") {
+            silence_start = Some(start);
+        } else if let Some(end) = log_value(line, "silence_end") {
+            if let Some(start) = silence_start.take() {
+                push("silence", start, end);
+            }
+        }
+    }
+
+    if let Some(start) = freeze_start {
+        push("freeze", start, duration);
+    }
+    if let Some(start) = silence_start {
+        push("silence", start, duration);
+    }
+
+    intervals.sort_by(|a, b| a.start.total_cmp(&b.start));
+    intervals
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QcReport {
+    pub passed: bool,
+    pub violations: Vec<QcInterval>,
+}
+
+#[derive(Args)]
+pub struct QcArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Write the report to a file instead of stdout")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 0.5,
+        help = "Shortest black segment reported, in seconds"
+    )]
+    pub black_duration: f64,
+    #[arg(
+        long,
+        default_value_t = 0.1,
+        help = "Pixel luminance below which a pixel counts as black (0.0 - 1.0)"
+    )]
+    pub black_threshold: f64,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "Shortest frozen segment reported, in seconds"
+    )]
+    pub freeze_duration: f64,
+    #[arg(
+        long,
+        default_value_t = 0.001,
+        help = "Noise tolerance for freeze detection (0.0 - 1.0)"
+    )]
+    pub freeze_noise: f64,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "Shortest silence reported, in seconds"
+    )]
+    pub silence_duration: f64,
+    #[arg(
+        long,
+        default_value = "-50dB",
+        allow_hyphen_values = true,
+        help = "Level below which audio counts as silence"
+    )]
+    pub silence_noise: String,
+    #[arg(
+        short,
+        long,
+        default_value = "text",
+        help = "Report format: text or json"
+    )]
+    pub format: String,
+}
+
+impl QcArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+        let duration = info.duration().unwrap_or_default();
+
+        let video_filter = format!(
+            "blackdetect=d={}:pix_th={},freezedetect=n={}:d={}",
+            self.black_duration, self.black_threshold, self.freeze_noise, self.freeze_duration
+        );
+        let audio_filter = format!(
+            "silencedetect=n={}:d={}",
+            self.silence_noise, self.silence_duration
+        );
+
+        let mut args = vec!["-i", self.input.to_str().unwrap(), "-vf", &video_filter];
+        if info.audio_stream().is_some() {
+            args.extend(["-af", &audio_filter]);
+        }
+        args.extend(["-f", "null", "-"]);
+        let log = run_ffmpeg_command_with_log(&args)?;
+
+        let violations = parse_qc_log(&log, duration);
+        let report = QcReport {
+            passed: violations.is_empty(),
+            violations,
+        };
+
+        let text = match self.format.as_str() {
+            "json" => serde_json::to_string_pretty(&report)? + "\n",
+            "text" => {
+                let mut text = String::new();
+                for v in &report.violations {
+                    text.push_str(&format!(
+                        "{:<8} {} - {} ({:.2}s)\n",
+                        v.kind,
+                        format_time(v.start),
+                        format_time(v.end),
+                        v.duration
+                    ));
+                }
+                text.push_str(if report.passed { "PASS\n" } else { "FAIL\n" });
+                text
+            }
+            _ => anyhow::bail!("Invalid format. Use text or json"),
+        };
+
+        match &self.output {
+            Some(output) => std::fs::write(output, text)?,
+            None => print!("{}", text),
+        }
+
+        if !report.passed {
+            anyhow::bail!("QC failed with {} violation(s)", report.violations.len());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FrameScore {
+    pub frame: u64,
+    pub time: f64,
+    pub psnr: f64,
+    pub ssim: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CompareReport {
+    pub frames: usize,
+    pub psnr: Option<f64>,
+    pub ssim: Option<f64>,
+    pub vmaf: Option<f64>,
+    pub worst: Vec<FrameScore>,
+    pub per_frame: Vec<FrameScore>,
+}
+
+/// Identical frames have infinite PSNR; cap it so reports stay numeric.
+const MAX_PSNR: f64 = 100.0;
+
+fn stats_value(line: &str, key: &str) -> Option<f64> {
+    let value = line
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix(key))?;
+    match value {
+        "inf" => Some(MAX_PSNR),
+        value => value.parse().ok(),
+    }
+}
+
+/// Parse a `psnr` or `ssim` filter stats file into `(frame, score)` pairs,
+/// reading `key` (e.g. `psnr_avg:` or `All:`) from each line.
+pub fn parse_stats_file(content: &str, key: &str) -> Vec<(u64, f64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let frame = stats_value(line, "n:")? as u64;
+            let score = stats_value(line, key)?;
+            Some((frame, score.min(MAX_PSNR)))
+        })
+        .collect()
+}
+
+/// Read the overall scores printed by the `psnr`, `ssim` and `libvmaf` filters.
+pub fn parse_quality_summary(log: &str) -> (Option<f64>, Option<f64>, Option<f64>) {
+    let mut psnr = None;
+    let mut ssim = None;
+    let mut vmaf = None;
+
+    for line in log.lines() {
+        if line.contains("PSNR ") {
+            psnr = stats_value(line, "average:");
+        } else if line.contains("SSIM ") {
+            ssim = stats_value(line, "All:");
+        } else if let Some(score) = line.split("VMAF score:").nth(1) {
+            vmaf = score.trim().parse().ok();
+        }
+    }
+
+    (psnr, ssim, vmaf)
+}
+
+#[derive(Args)]
+pub struct CompareArgs {
+    #[arg(short = 'a', long, help = "Reference (original) video")]
+    pub reference: PathBuf,
+    #[arg(short = 'b', long, help = "Distorted (encoded) video")]
+    pub distorted: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Write the JSON report to a file instead of stdout"
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(long, default_value_t = 5, help = "Number of worst frames to list")]
+    pub worst: usize,
+    #[arg(long, help = "Also compute VMAF when FFmpeg has libvmaf")]
+    pub vmaf: bool,
+}
+
+impl CompareArgs {
+    pub fn execute(&self) -> Result<()> {
+        let reference = probe(&self.reference)?;
+        let (width, height) = reference
+            .dimensions()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine reference video size"))?;
+        let fps = reference.frame_rate().unwrap_or(25.0);
+        let rate = reference
+            .video_stream()
+            .and_then(|s| s.r_frame_rate.clone())
+            .unwrap_or_else(|| "25".to_string());
+
+        let vmaf = self.vmaf && ffmpeg_has_filter("libvmaf");
+        if self.vmaf && !vmaf {
+            eprintln!("Warning: FFmpeg was built without libvmaf, skipping VMAF");
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let psnr_log = temp_dir.path().join("psnr.log");
+        let ssim_log = temp_dir.path().join("ssim.log");
+        let branches = if vmaf { 3 } else { 2 };
+
+        // Scale and retime the distorted stream to line up with the reference
+        let mut filter = format!(
+            "[0:v]scale={}:{}:flags=bicubic,fps={},setpts=PTS-STARTPTS,split={}[d0][d1]{};\
+             [1:v]setpts=PTS-STARTPTS,split={}[r0][r1]{};\
+             [d0][r0]psnr=stats_file={};[d1][r1]ssim=stats_file={}",
+            width,
+            height,
+            rate,
+            branches,
+            if vmaf { "[d2]" } else { "" },
+            branches,
+            if vmaf { "[r2]" } else { "" },
+            escape_filter_arg(psnr_log.to_str().unwrap()),
+            escape_filter_arg(ssim_log.to_str().unwrap())
+        );
+        if vmaf {
+            filter.push_str(";[d2][r2]libvmaf");
+        }
+
+        let args = [
+            "-i",
+            self.distorted.to_str().unwrap(),
+            "-i",
+            self.reference.to_str().unwrap(),
+            "-lavfi",
+            &filter,
+            "-f",
+            "null",
+            "-",
+        ];
+        let log = run_ffmpeg_command_with_log(&args)?;
+        let (psnr, ssim, vmaf) = parse_quality_summary(&log);
+
+        let psnr_frames = parse_stats_file(&std::fs::read_to_string(&psnr_log)?, "psnr_avg:");
+        let ssim_frames = parse_stats_file(&std::fs::read_to_string(&ssim_log)?, "All:");
+        let per_frame: Vec<FrameScore> = psnr_frames
+            .iter()
+            .zip(ssim_frames.iter())
+            .map(|(&(frame, psnr), &(_, ssim))| FrameScore {
+                frame,
+                time: frame.saturating_sub(1) as f64 / fps,
+                psnr,
+                ssim,
+            })
+            .collect();
+
+        let mut worst = per_frame.clone();
+        worst.sort_by(|a, b| a.ssim.total_cmp(&b.ssim));
+        worst.truncate(self.worst);
+
+        let report = CompareReport {
+            frames: per_frame.len(),
+            psnr,
+            ssim,
+            vmaf,
+            worst,
+            per_frame,
+        };
+
+        let json = serde_json::to_string_pretty(&report)? + "\n";
+        match &self.output {
+            Some(output) => std::fs::write(output, json)?,
+            None => print!("{}", json),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct FadeVideoArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(long, help = "Fade in duration in seconds")]
+    pub fade_in: Option<f64>,
+    #[arg(long, help = "Fade out duration in seconds")]
+    pub fade_out: Option<f64>,
+    #[arg(
+        long,
+        default_value = "tri",
+        help = "Audio fade curve: tri, qsin, hsin, esin, log, ipar, qua, cub, squ, cbr, par, exp"
+    )]
+    pub curve: String,
+}
+
+impl FadeVideoArgs {
+    pub fn execute(&self) -> Result<()> {
+        if self.fade_in.is_none() && self.fade_out.is_none() {
+            anyhow::bail!("Specify --fade-in and/or --fade-out");
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+        let transform = Transform {
+            fade: Some(Fade {
+                fade_in: self.fade_in,
+                fade_out: self.fade_out,
+                curve: self.curve.clone(),
+            }),
+            ..Default::default()
+        };
+        transform_video(&self.input, &output, &transform)
+    }
+}
+
+/// Codec arguments to stream-copy everything into `output`, converting text
+/// subtitles when the target container can't hold them as-is.
+pub fn copy_codec_args(output: &Path) -> Vec<&'static str> {
+    let ext = output.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match subtitle_codec_by_ext(ext) {
+        "copy" => vec!["-c", "copy"],
+        codec => vec!["-c", "copy", "-c:s", codec],
+    }
+}
+
+/// A per-stream option given as `<index>=<value>`, e.g. `1=eng`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamValue {
+    pub index: usize,
+    pub value: String,
+}
+
+impl FromStr for StreamValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (index, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid stream value: {}. Use <index>=<value>", s))?;
+        let index = index
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid stream index: {}", index))?;
+        Ok(Self {
+            index,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Input stream indices to write, in output order. `keep` selects and orders
+/// streams explicitly; otherwise every stream but those in `drop` is kept.
+pub fn stream_order(available: &[usize], keep: &[usize], drop: &[usize]) -> Result<Vec<usize>> {
+    for index in keep.iter().chain(drop) {
+        if !available.contains(index) {
+            anyhow::bail!("Stream {} does not exist", index);
+        }
+    }
+
+    let order: Vec<usize> = if keep.is_empty() {
+        available
+            .iter()
+            .copied()
+            .filter(|i| !drop.contains(i))
+            .collect()
+    } else {
+        keep.iter().copied().filter(|i| !drop.contains(i)).collect()
+    };
+
+    if order.is_empty() {
+        anyhow::bail!("No streams left to write");
+    }
+    Ok(order)
+}
+
+/// Disposition changes for output streams, as `(output index, value)`.
+/// Values are relative (`+default`, `-default`) so other flags such as
+/// `hearing_impaired` survive. Marking a stream default clears the default
+/// flag on other streams of the same type; streams that aren't affected are
+/// left untouched.
+pub fn stream_dispositions(
+    streams: &[(usize, &str)],
+    default: &[usize],
+    forced: &[usize],
+) -> Vec<(usize, String)> {
+    let default_types: Vec<&str> = streams
+        .iter()
+        .filter(|(index, _)| default.contains(index))
+        .map(|(_, kind)| *kind)
+        .collect();
+
+    streams
+        .iter()
+        .enumerate()
+        .filter(|(_, (index, kind))| default_types.contains(kind) || forced.contains(index))
+        .map(|(position, (index, kind))| {
+            let mut value = String::new();
+            if default.contains(index) {
+                value.push_str("+default");
+            } else if default_types.contains(kind) {
+                value.push_str("-default");
+            }
+            if forced.contains(index) {
+                value.push_str("+forced");
+            }
+            (position, value)
+        })
+        .collect()
+}
+
+#[derive(Args)]
+pub struct StreamsArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long, help = "Output file (omit to list streams)")]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "Streams to keep, in output order (e.g. 0,2,1)"
+    )]
+    pub keep: Vec<usize>,
+    #[arg(short, long, value_delimiter = ',', help = "Streams to drop")]
+    pub drop: Vec<usize>,
+    #[arg(long, help = "Set a stream's language as <index>=<lang> (e.g. 1=eng)")]
+    pub language: Vec<StreamValue>,
+    #[arg(long, help = "Set a stream's title as <index>=<title>")]
+    pub title: Vec<StreamValue>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Mark streams as default for their type"
+    )]
+    pub default: Vec<usize>,
+    #[arg(long, value_delimiter = ',', help = "Mark streams as forced")]
+    pub forced: Vec<usize>,
+}
+
+impl StreamsArgs {
+    pub fn execute(&self) -> Result<()> {
+        let info = probe(&self.input)?;
+
+        let output = match &self.output {
+            Some(output) => output,
+            None => {
+                for stream in &info.streams {
+                    println!("{}", stream_summary(stream));
+                }
+                return Ok(());
+            }
+        };
+
+        let available: Vec<usize> = info.streams.iter().map(|s| s.index).collect();
+        let order = stream_order(&available, &self.keep, &self.drop)?;
+        let kinds: Vec<(usize, &str)> = order
+            .iter()
+            .map(|&index| {
+                let kind = info
+                    .streams
+                    .iter()
+                    .find(|s| s.index == index)
+                    .and_then(|s| s.codec_type.as_deref())
+                    .unwrap_or("");
+                (index, kind)
+            })
+            .collect();
+
+        let mut args: Vec<String> = vec!["-i".into(), self.input.to_str().unwrap().into()];
+        for index in &order {
+            args.extend(["-map".into(), format!("0:{}", index)]);
+        }
+        args.extend(copy_codec_args(output).into_iter().map(String::from));
+
+        // Tags address streams by their position in the output
+        let position = |index: usize| -> Result<usize> {
+            order
+                .iter()
+                .position(|&i| i == index)
+                .ok_or_else(|| anyhow::anyhow!("Stream {} is not in the output", index))
+        };
+        for tag in &self.language {
+            args.extend([
+                format!("-metadata:s:{}", position(tag.index)?),
+                format!("language={}", tag.value),
+            ]);
+        }
+        for tag in &self.title {
+            args.extend([
+                format!("-metadata:s:{}", position(tag.index)?),
+                format!("title={}", tag.value),
+            ]);
+        }
+        for index in self.default.iter().chain(&self.forced) {
+            position(*index)?;
+        }
+        for (position, value) in stream_dispositions(&kinds, &self.default, &self.forced) {
+            args.extend([format!("-disposition:{}", position), value]);
+        }
+
+        args.extend(["-y".into(), output.to_str().unwrap().into()]);
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run_ffmpeg_command(&args)
+    }
+}
+
+/// One-line description of a stream for `streams` listings.
+pub fn stream_summary(stream: &StreamInfo) -> String {
+    let mut line = format!(
+        "#{} {} {}",
+        stream.index,
+        stream.codec_type.as_deref().unwrap_or("unknown"),
+        stream.codec_name.as_deref().unwrap_or("unknown")
+    );
+    if let (Some(width), Some(height)) = (stream.width, stream.height) {
+        line.push_str(&format!(" {}x{}", width, height));
+    }
+    if let Some(channels) = stream.channels {
+        line.push_str(&format!(" {}ch", channels));
+    }
+    if let Some(language) = stream.tags.get("language") {
+        line.push_str(&format!(" [{}]", language));
+    }
+    if let Some(title) = stream.tags.get("title") {
+        line.push_str(&format!(" \"{}\"", title));
+    }
+
+    let mut flags: Vec<&str> = stream
+        .disposition
+        .iter()
+        .filter(|(name, &value)| value == 1 && (*name == "default" || *name == "forced"))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    flags.sort();
+    if !flags.is_empty() {
+        line.push_str(&format!(" ({})", flags.join(", ")));
+    }
+    line
+}
+
+#[derive(Args)]
+pub struct StabilizeArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "How shaky the footage is (1-10), higher detects larger motion"
+    )]
+    pub shakiness: u32,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "Frames averaged on each side for camera path smoothing"
+    )]
+    pub smoothing: u32,
+    #[arg(
+        long,
+        help = "Fixed zoom in percent to hide borders (default: automatic optimal zoom)"
+    )]
+    pub zoom: Option<f64>,
+    #[arg(
+        long,
+        help = "Use FFmpeg's deshake filter even if vidstab is available"
+    )]
+    pub deshake: bool,
+}
+
+impl StabilizeArgs {
+    /// `vidstabdetect` filter for the analysis pass.
+    pub fn detect_filter(&self, transforms: &Path) -> String {
+        format!(
+            "vidstabdetect=shakiness={}:accuracy=15:result={}",
+            self.shakiness,
+            escape_filter_arg(transforms.to_str().unwrap())
+        )
+    }
+
+    /// `vidstabtransform` filter for the second pass, sharpened afterwards to
+    /// offset the interpolation softening.
+    pub fn transform_filter(&self, transforms: &Path) -> String {
+        let zoom = match self.zoom {
+            Some(zoom) => format!("optzoom=0:zoom={}", zoom),
+            None => "optzoom=1".to_string(),
+        };
+        format!(
+            "vidstabtransform=input={}:smoothing={}:{},unsharp=5:5:0.8:3:3:0.4",
+            escape_filter_arg(transforms.to_str().unwrap()),
+            self.smoothing,
+            zoom
+        )
+    }
+
+    /// Single-pass `deshake` filter, with the search range scaled by shakiness.
+    pub fn deshake_filter(&self) -> String {
+        let range = (self.shakiness.clamp(1, 10) * 6).min(64);
+        let mut filter = format!("deshake=rx={}:ry={}", range, range);
+        if let Some(zoom) = self.zoom.filter(|z| *z > 0.0) {
+            let scale = 1.0 + zoom / 100.0;
+            filter.push_str(&format!(
+                ",scale=trunc(iw*{}/2)*2:-2,crop=trunc(iw/{}/2)*2:trunc(ih/{}/2)*2",
+                scale, scale, scale
+            ));
+        }
+        filter
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        if !(1..=10).contains(&self.shakiness) {
+            anyhow::bail!("Shakiness must be between 1 and 10");
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        // Motion vectors from the first pass, read back by the second
+        let transforms = NamedTempFile::new()?;
+        let vidstab = ffmpeg_has_filter("vidstabdetect") && ffmpeg_has_filter("vidstabtransform");
+        let filter = if vidstab && !self.deshake {
+            println!("Analyzing motion...");
+            run_ffmpeg_command(&[
+                "-i",
+                self.input.to_str().unwrap(),
+                "-vf",
+                &self.detect_filter(transforms.path()),
+                "-an",
+                "-f",
+                "null",
+                "-",
+            ])?;
+            self.transform_filter(transforms.path())
+        } else {
+            if !self.deshake {
+                println!("vidstab filters not available in this FFmpeg build, using deshake");
+            }
+            self.deshake_filter()
+        };
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+/// Presets built into FFmpeg's `curves` filter.
+const CURVES_PRESETS: [&str; 10] = [
+    "color_negative",
+    "cross_process",
+    "darker",
+    "increase_contrast",
+    "lighter",
+    "linear_contrast",
+    "medium_contrast",
+    "negative",
+    "strong_contrast",
+    "vintage",
+];
+
+#[derive(Args)]
+pub struct ColorArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Brightness multiplier (1.0 is unchanged), as in image filter"
+    )]
+    pub brightness: Option<f32>,
+    #[arg(long, help = "Contrast (-1000 to 1000, 1.0 is unchanged)")]
+    pub contrast: Option<f32>,
+    #[arg(
+        long,
+        help = "Saturation (0.0 to 3.0, 0.0 is grayscale, 1.0 is unchanged)"
+    )]
+    pub saturation: Option<f32>,
+    #[arg(long, help = "Gamma (0.1 to 10, 1.0 is unchanged)")]
+    pub gamma: Option<f32>,
+    #[arg(
+        long,
+        help = "White balance: warm, cool, or a colour temperature in Kelvin (e.g. 5600)"
+    )]
+    pub white_balance: Option<String>,
+    #[arg(
+        long,
+        help = "Curves preset: vintage, lighter, darker, strong_contrast, cross_process, ..."
+    )]
+    pub curves: Option<String>,
+    #[arg(long, help = "3D LUT file (.cube) to apply")]
+    pub lut: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Filter or chain, as for images (e.g. grayscale,blur=2,contrast=1.2)"
+    )]
+    pub filter: Option<String>,
+    #[arg(long, help = "Filter intensity, used by filters given without a value")]
+    pub intensity: Option<f32>,
+}
+
+impl ColorArgs {
+    pub fn filter_graph(&self) -> Result<String> {
+        let mut filters = Vec::new();
+
+        if let Some(balance) = &self.white_balance {
+            filters.push(match balance.as_str() {
+                "warm" => "colorbalance=rs=0.1:bs=-0.1:rm=0.05:bm=-0.05".to_string(),
+                "cool" => "colorbalance=rs=-0.1:bs=0.1:rm=-0.05:bm=0.05".to_string(),
+                kelvin => match kelvin.trim_end_matches(['K', 'k']).parse::<u32>() {
+                    Ok(kelvin) if (1000..=40000).contains(&kelvin) => {
+                        format!("colortemperature=temperature={}", kelvin)
+                    }
+                    _ => anyhow::bail!(
+                        "Invalid white balance: {}. Use warm, cool, or 1000-40000 Kelvin",
+                        balance
+                    ),
+                },
+            });
+        }
+
+        // Brightness is a multiplier, rendered like the shared filter
+        if let Some(brightness) = self.brightness {
+            if brightness < 0.0 {
+                anyhow::bail!("Brightness must be 0 or greater");
+            }
+            filters.push(Filter::Brightness(brightness).to_ffmpeg());
+        }
+
+        let mut eq = Vec::new();
+        for (name, label, value, range) in [
+            ("contrast", "Contrast", self.contrast, -1000.0..=1000.0),
+            ("saturation", "Saturation", self.saturation, 0.0..=3.0),
+            ("gamma", "Gamma", self.gamma, 0.1..=10.0),
+        ] {
+            let Some(value) = value else { continue };
+            if !range.contains(&value) {
+                anyhow::bail!(
+                    "{} must be between {} and {}",
+                    label,
+                    range.start(),
+                    range.end()
+                );
+            }
+            eq.push(format!("{}={}", name, value));
+        }
+        if !eq.is_empty() {
+            filters.push(format!("eq={}", eq.join(":")));
+        }
+
+        if let Some(preset) = &self.curves {
+            if !CURVES_PRESETS.contains(&preset.as_str()) {
+                anyhow::bail!(
+                    "Invalid curves preset: {}. Use {}",
+                    preset,
+                    CURVES_PRESETS.join(", ")
+                );
+            }
+            filters.push(format!("curves=preset={}", preset));
+        }
+
+        if let Some(lut) = &self.lut {
+            filters.push(format!(
+                "lut3d=file={}",
+                escape_filter_arg(lut.to_str().unwrap())
+            ));
+        }
+
+        if let Some(filter) = &self.filter {
+            filters.push(filters_to_ffmpeg(&Filter::parse_chain(
+                filter,
+                self.intensity,
+            )?));
+        }
+
+        if filters.is_empty() {
+            anyhow::bail!("No adjustments given");
+        }
+        Ok(filters.join(","))
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let filter = self.filter_graph()?;
+        if let Some(lut) = &self.lut {
+            if !lut.exists() {
+                anyhow::bail!("LUT file not found: {}", lut.display());
+            }
+        }
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+/// Denoise filter for a method (`hqdn3d`, `nlmeans`) and strength preset
+/// (`light`, `medium`, `strong`).
+pub fn denoise_filter(method: &str, strength: &str) -> Result<String> {
+    let level = match strength {
+        "light" => 0,
+        "medium" => 1,
+        "strong" => 2,
+        _ => anyhow::bail!("Invalid strength. Use light, medium, or strong"),
+    };
+    let filter = match method {
+        // Spatial luma/chroma, temporal luma/chroma; medium is FFmpeg's default
+        "hqdn3d" => ["hqdn3d=2:1.5:3:2.25", "hqdn3d=4:3:6:4.5", "hqdn3d=8:6:12:9"][level],
+        // Slower, better at keeping detail
+        "nlmeans" => ["nlmeans=s=1.5", "nlmeans=s=3", "nlmeans=s=6"][level],
+        _ => anyhow::bail!("Invalid denoise method. Use hqdn3d or nlmeans"),
+    };
+    Ok(filter.to_string())
+}
+
+/// Unsharp mask on luma only, with a square matrix of `radius` pixels.
+pub fn sharpen_filter(amount: f32, radius: u32) -> Result<String> {
+    if radius.is_multiple_of(2) || !(3..=23).contains(&radius) {
+        anyhow::bail!("Radius must be an odd number between 3 and 23");
+    }
+    if !(-2.0..=5.0).contains(&amount) {
+        anyhow::bail!("Amount must be between -2.0 and 5.0");
+    }
+    Ok(format!(
+        "unsharp=lx={}:ly={}:la={}:cx=3:cy=3:ca=0",
+        radius, radius, amount
+    ))
+}
+
+#[derive(Args)]
+pub struct DenoiseArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value = "hqdn3d",
+        help = "Denoise method: hqdn3d (fast) or nlmeans (slow, keeps more detail)"
+    )]
+    pub method: String,
+    #[arg(
+        short,
+        long,
+        default_value = "medium",
+        help = "Strength: light, medium, or strong"
+    )]
+    pub strength: String,
+}
+
+impl DenoiseArgs {
+    pub fn execute(&self) -> Result<()> {
+        let filter = denoise_filter(&self.method, &self.strength)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+#[derive(Args)]
+pub struct SharpenArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value_t = 1.0,
+        help = "Sharpening amount (-2.0 to 5.0), negative values blur"
+    )]
+    pub amount: f32,
+    #[arg(
+        short,
+        long,
+        default_value_t = 5,
+        help = "Matrix size in pixels (odd, 3-23)"
+    )]
+    pub radius: u32,
+}
+
+impl SharpenArgs {
+    pub fn execute(&self) -> Result<()> {
+        let filter = sharpen_filter(self.amount, self.radius)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+#[derive(Args)]
+pub struct CleanupArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value = "light",
+        help = "Picture denoise strength: light, medium, or strong"
+    )]
+    pub strength: String,
+    #[arg(
+        long,
+        default_value_t = 12.0,
+        help = "Audio noise reduction in dB (0.01-97)"
+    )]
+    pub noise_reduction: f32,
+}
+
+impl CleanupArgs {
+    pub fn execute(&self) -> Result<()> {
+        let video_filter = denoise_filter("hqdn3d", &self.strength)?;
+        if !(0.01..=97.0).contains(&self.noise_reduction) {
+            anyhow::bail!("Noise reduction must be between 0.01 and 97 dB");
+        }
+        let audio_filter = format!("afftdn=nr={}", self.noise_reduction);
+        let has_audio = probe(&self.input)?.audio_stream().is_some();
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        let mut args = vec!["-i", self.input.to_str().unwrap(), "-vf", &video_filter];
+        if has_audio {
+            args.extend_from_slice(&["-af", &audio_filter]);
+        } else {
+            println!("No audio track found, only denoising the picture");
+        }
+        args.extend_from_slice(&["-y", output.to_str().unwrap()]);
+
+        run_ffmpeg_command(&args)
+    }
+}
+
+/// A region to redact in a video: a fixed rectangle, optionally limited to a
+/// time range (`WxH+X+Y[@START-END]`), or keyframed rectangles interpolated
+/// over time (`T=WxH+X+Y;T=WxH+X+Y;...`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Redaction {
+    Fixed {
+        rect: Rect,
+        start: Option<f64>,
+        end: Option<f64>,
+    },
+    Keyframed(Vec<(f64, Rect)>),
+}
+
+impl FromStr for Redaction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.contains('=') {
+            let mut keyframes = s
+                .split(';')
+                .filter(|k| !k.trim().is_empty())
+                .map(|keyframe| {
+                    let (time, rect) = keyframe.split_once('=').ok_or_else(|| {
+                        anyhow::anyhow!("Invalid keyframe: {}. Use TIME=WxH+X+Y", keyframe)
+                    })?;
+                    Ok((parse_time(time)?, rect.parse()?))
+                })
+                .collect::<Result<Vec<(f64, Rect)>>>()?;
+            if keyframes.len() < 2 {
+                anyhow::bail!("Keyframed regions need at least two keyframes");
+            }
+            keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+            return Ok(Self::Keyframed(keyframes));
+        }
+
+        let (rect, range) = match s.split_once('@') {
+            Some((rect, range)) => (rect, Some(range)),
+            None => (s, None),
+        };
+        let (start, end) = match range {
+            Some(range) => {
+                let (start, end) = range.split_once('-').ok_or_else(|| {
+                    anyhow::anyhow!("Invalid time range: {}. Use START-END", range)
+                })?;
+                let parse = |t: &str| -> Result<Option<f64>> {
+                    match t.trim() {
+                        "" => Ok(None),
+                        t => parse_time(t).map(Some),
+                    }
+                };
+                (parse(start)?, parse(end)?)
+            }
+            None => (None, None),
+        };
+        Ok(Self::Fixed {
+            rect: rect.parse()?,
+            start,
+            end,
+        })
+    }
+}
+
+impl Redaction {
+    /// Region size and x/y expressions of `t` (top-left corner), clipped to
+    /// the frame.
+    fn geometry(&self, frame: (u32, u32)) -> Option<(u32, u32, String, String)> {
+        match self {
+            Self::Fixed { rect, .. } => {
+                let rect = rect.clip(frame.0, frame.1)?;
+                Some((
+                    rect.width,
+                    rect.height,
+                    rect.x.to_string(),
+                    rect.y.to_string(),
+                ))
+            }
+            Self::Keyframed(keyframes) => {
+                // The box keeps the largest keyframe size and follows the
+                // interpolated centre
+                let width = keyframes.iter().map(|(_, r)| r.width).max()?.min(frame.0);
+                let height = keyframes.iter().map(|(_, r)| r.height).max()?.min(frame.1);
+                let centers = |center: fn(&Rect) -> f64| {
+                    keyframes
+                        .iter()
+                        .map(|(t, r)| (*t, center(r)))
+                        .collect::<Vec<_>>()
+                };
+                let cx = crop_path_expression(&centers(|r| r.x as f64 + r.width as f64 / 2.0));
+                let cy = crop_path_expression(&centers(|r| r.y as f64 + r.height as f64 / 2.0));
+                Some((
+                    width,
+                    height,
+                    format!("clip({}-{},0,{})", cx, width / 2, frame.0 - width),
+                    format!("clip({}-{},0,{})", cy, height / 2, frame.1 - height),
+                ))
+            }
+        }
+    }
+
+    fn enable_expression(&self) -> Option<String> {
+        match self {
+            Self::Fixed { start, end, .. } => enable_expression(*start, *end),
+            Self::Keyframed(keyframes) => Some(format!(
+                "between(t,{},{})",
+                keyframes.first()?.0,
+                keyframes.last()?.0
+            )),
+        }
+    }
+}
+
+/// Smallest box blur radius that still hides a region; below it `blur`
+/// falls back to a solid block.
+pub const MIN_REDACT_BLUR: u32 = 4;
+
+/// Filter graph reading `[0:v]` and writing `[v]` that crops each region,
+/// blurs (`strength` is the box radius) or pixelates it (`strength` is the
+/// block size) and overlays it back in place.
+pub fn redact_filter_graph(
+    redactions: &[Redaction],
+    method: &str,
+    strength: u32,
+    frame: (u32, u32),
+) -> Result<String> {
+    if !matches!(method, "blur" | "pixelate") {
+        anyhow::bail!("Invalid redaction method. Use blur or pixelate");
+    }
+    // A zero radius or one-pixel blocks would leave the region readable
+    if strength == 0 || (method == "pixelate" && strength < 2) {
+        anyhow::bail!("Blur strength must be at least 1 and pixelate strength at least 2");
+    }
+
+    let regions: Vec<_> = redactions
+        .iter()
+        .map(|r| {
+            r.geometry(frame)
+                .map(|g| (g, r.enable_expression()))
+                .ok_or_else(|| anyhow::anyhow!("Region {:?} is outside the video", r))
+        })
+        .collect::<Result<_>>()?;
+
+    let mut graph = format!("[0:v]split={}[base]", regions.len() + 1);
+    for i in 0..regions.len() {
+        graph.push_str(&format!("[r{}]", i));
+    }
+
+    let mut current = "base".to_string();
+    for (i, ((width, height, x, y), enable)) in regions.iter().enumerate() {
+        // Chroma planes are half size, so keep the blur radius within them.
+        // Regions too small for a useful blur are pixelated into one block.
+        let radius = strength.min(width.min(height) / 4);
+        let effect = if method == "blur" && (radius >= MIN_REDACT_BLUR || radius == strength) {
+            format!("boxblur={}:2", radius)
+        } else {
+            let block = if method == "blur" {
+                *width.max(height)
+            } else {
+                strength
+            };
+            format!(
+                "scale={}:{}:flags=area,scale={}:{}:flags=neighbor",
+                width.div_ceil(block),
+                height.div_ceil(block),
+                width,
+                height
+            )
+        };
+        let next = if i + 1 == regions.len() {
+            "v".to_string()
+        } else {
+            format!("v{}", i)
+        };
+        let enable = enable
+            .as_ref()
+            .map(|e| format!(":enable='{}'", e))
+            .unwrap_or_default();
+
+        graph.push_str(&format!(
+            ";[r{i}]crop={width}:{height}:x='{x}':y='{y}',{effect}[b{i}];[{current}][b{i}]overlay=x='{x}':y='{y}'{enable}[{next}]"
+        ));
+        current = next;
+    }
+    Ok(graph)
+}
+
+#[derive(Args)]
+pub struct RedactArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "Region as WxH+X+Y[@START-END], or keyframes as T=WxH+X+Y;T=WxH+X+Y (repeatable)"
+    )]
+    pub region: Vec<Redaction>,
+    #[arg(
+        short,
+        long,
+        default_value = "blur",
+        help = "Redaction method: blur or pixelate"
+    )]
+    pub method: String,
+    #[arg(
+        short,
+        long,
+        help = "Blur radius or pixelation block size (default 10 / 16)"
+    )]
+    pub strength: Option<u32>,
+}
+
+impl RedactArgs {
+    pub fn execute(&self) -> Result<()> {
+        let frame = probe(&self.input)?
+            .dimensions()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine video dimensions"))?;
+        let strength = self
+            .strength
+            .unwrap_or(if self.method == "pixelate" { 16 } else { 10 });
+        let graph = redact_filter_graph(&self.region, &self.method, strength, frame)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-filter_complex",
+            &graph,
+            "-map",
+            "[v]",
+            "-map",
+            "0:a?",
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+/// Timelapse filter keeping every `factor`th frame (`drop`) or averaging each
+/// group of `factor` frames (`blend`), at the source frame rate.
+pub fn timelapse_filter(factor: u32, mode: &str) -> Result<String> {
+    if factor < 2 {
+        anyhow::bail!("Factor must be at least 2");
+    }
+    let select = format!("select='not(mod(n,{}))',setpts=N/FRAME_RATE/TB", factor);
+    let filter = match mode {
+        "drop" => select,
+        // tmix averages at most 1024 frames
+        "blend" if factor <= 1024 => format!("tmix=frames={},{}", factor, select),
+        "blend" => anyhow::bail!("Blend mode supports factors up to 1024"),
+        _ => anyhow::bail!("Invalid timelapse mode. Use drop or blend"),
+    };
+    Ok(filter)
+}
+
+/// Frame rate conversion filter: `drop` drops or duplicates frames, `blend`
+/// cross-fades neighbouring frames, `interpolate` synthesises frames from
+/// motion estimation (slow).
+pub fn fps_filter(rate: &str, mode: &str) -> Result<String> {
+    match parse_rational(rate) {
+        Some(value) if value > 0.0 => {}
+        _ => anyhow::bail!("Invalid frame rate: {}", rate),
+    }
+    let filter = match mode {
+        "drop" => format!("fps={}", rate),
+        "blend" => format!("minterpolate=fps={}:mi_mode=blend", rate),
+        "interpolate" => format!(
+            "minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1",
+            rate
+        ),
+        _ => anyhow::bail!("Invalid fps mode. Use drop, blend, or interpolate"),
+    };
+    Ok(filter)
+}
+
+#[derive(Args)]
+pub struct TimelapseArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(short, long, help = "Speed-up factor (e.g. 30 keeps one frame in 30)")]
+    pub factor: u32,
+    #[arg(
+        short,
+        long,
+        default_value = "drop",
+        help = "Timelapse mode: drop (every Nth frame) or blend (average frames)"
+    )]
+    pub mode: String,
+}
+
+impl TimelapseArgs {
+    pub fn execute(&self) -> Result<()> {
+        let filter = timelapse_filter(self.factor, &self.mode)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-an", // Sped-up audio is rarely useful
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
+
+#[derive(Args)]
+pub struct FpsArgs {
+    #[arg(short, long)]
+    pub input: PathBuf,
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    #[arg(short, long, help = "Target frame rate (e.g. 30, 29.97 or 30000/1001)")]
+    pub to: String,
+    #[arg(
+        short,
+        long,
+        default_value = "drop",
+        help = "Conversion mode: drop (drop/duplicate), blend, or interpolate (motion)"
+    )]
+    pub mode: String,
+}
+
+impl FpsArgs {
+    pub fn execute(&self) -> Result<()> {
+        let filter = fps_filter(&self.to, &self.mode)?;
+
+        // Get output or prompt for one
+        let output = get_file_arg(FileType::Video, &self.output)?;
+
+        // The duration doesn't change, so the audio can be kept as-is
+        run_ffmpeg_command(&[
+            "-i",
+            self.input.to_str().unwrap(),
+            "-vf",
+            &filter,
+            "-c:a",
+            "copy",
+            "-y",
+            output.to_str().unwrap(),
+        ])
+    }
+}
@@ -7,7 +7,7 @@ fn test_cli_convert_mp3_to_wav() {
     let output = create_temp_file(&"wav".to_string());
 
     let mut cmd = Command::cargo_bin("omu").unwrap();
-    cmd.args(&[
+    cmd.args([
         "convert",
         "-i",
         "samples/source.mp3",
@@ -31,7 +31,7 @@ fn test_cli_extract_audio_from_video() {
     let output = create_temp_file(&"wav".to_string());
 
     let mut cmd = Command::cargo_bin("omu").unwrap();
-    cmd.args(&[
+    cmd.args([
         "video",
         "extract-audio",
         "-i",
@@ -1,4 +1,8 @@
-use omu::audio::{CombineAudioArgs, VolumeArgs};
+use omu::audio::{
+    afade_filter, normalize_output_args, parse_loudnorm_stats, parse_max_volume, ChannelGain,
+    CombineAudioArgs, Gain, NormalizeArgs, VisualizeArgs, VolumeArgs,
+};
+use omu::video::Anchor;
 use std::path::Path;
 use tempfile::tempdir;
 
@@ -37,7 +41,9 @@ fn test_change_audio_volume() {
     let args = VolumeArgs {
         input: input.to_path_buf(),
         output: Some(output.clone()),
-        volume: 2.0,
+        volume: "2.0".parse().unwrap(),
+        channel_gain: vec![],
+        limit: None,
     };
     args.execute().unwrap();
 
@@ -49,3 +55,145 @@ fn test_change_audio_volume() {
     let mp3_magic = b"ID3"; // First 3 bytes of an MP3 file.
     assert!(omu::utils::verify_magic_bytes(&output, mp3_magic).unwrap());
 }
+
+#[test]
+fn test_afade_filter() {
+    // Test building fade in/out filters from an audio duration.
+    assert_eq!(
+        afade_filter(None, Some(3.0), "exp", 60.0).unwrap(),
+        "afade=t=out:st=57:d=3:curve=exp"
+    );
+    assert_eq!(afade_filter(None, None, "tri", 60.0), None);
+}
+
+#[test]
+fn test_visualize_filter_graph() {
+    // Test the audiogram graph for a solid background and a bad layout.
+    let mut args = VisualizeArgs {
+        input: Path::new("samples/source.mp3").to_path_buf(),
+        output: None,
+        background: None,
+        background_color: "navy".to_string(),
+        style: "bars".to_string(),
+        color: "0xFFCC00".to_string(),
+        size: "1080x1080".to_string(),
+        visual_size: "1000x300".to_string(),
+        position: Anchor::Bottom,
+        margin: 60,
+        fps: 25,
+    };
+
+    assert_eq!(
+        args.filter_graph().unwrap(),
+        "[1:a]showfreqs=s=1000x300:mode=bar:ascale=log:fscale=log:colors=0xFFCC00:rate=25,format=rgba[vis];[0:v]scale=1080:1080:force_original_aspect_ratio=increase,crop=1080:1080,setsar=1,fps=25[bg];[bg][vis]overlay=(W-w)/2:H-h-60:shortest=1,format=yuv420p[v]"
+    );
+
+    args.visual_size = "1200x300".to_string();
+    assert!(args.filter_graph().is_err());
+}
+
+#[test]
+fn test_loudnorm_two_pass_filter() {
+    // Test parsing the loudnorm measurements and feeding them into the second pass.
+    let log = r#"[Parsed_loudnorm_0 @ 0x55d] 
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.10",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.80",
+	"output_thresh" : "-28.06",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+    let stats = parse_loudnorm_stats(log).unwrap();
+    assert_eq!(stats.input_i, "-27.61");
+    assert_eq!(stats.output_i, "-16.58");
+
+    let mut args = NormalizeArgs {
+        input: Path::new("samples/source.mp3").to_path_buf(),
+        output: None,
+        target: -16.0,
+        true_peak: -1.5,
+        lra: 11.0,
+        peak: None,
+    };
+    assert_eq!(
+        args.loudnorm_filter(None).unwrap(),
+        "loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json"
+    );
+    assert_eq!(
+        args.loudnorm_filter(Some(&stats)).unwrap(),
+        "loudnorm=I=-16:TP=-1.5:LRA=11:measured_I=-27.61:measured_TP=-4.47:measured_LRA=18.10:measured_thresh=-39.20:offset=0.58:linear=true:print_format=json"
+    );
+
+    // Only the measured track is normalized; videos keep their other streams
+    assert_eq!(
+        normalize_output_args("volume=2dB", "44100", "aac", true).join(" "),
+        "-map 0 -c copy -filter:a:0 volume=2dB -c:a:0 aac -ar:a:0 44100"
+    );
+    assert_eq!(
+        normalize_output_args("volume=2dB", "44100", "libmp3lame", false).join(" "),
+        "-map 0:a:0 -af volume=2dB -c:a libmp3lame -ar 44100"
+    );
+
+    args.target = 0.0;
+    assert!(args.loudnorm_filter(None).is_err());
+    assert!(parse_loudnorm_stats("no stats here").is_err());
+}
+
+#[test]
+fn test_parse_max_volume() {
+    // Test reading the peak level from volumedetect output.
+    let log = "[Parsed_volumedetect_0 @ 0x1] mean_volume: -21.3 dB\n[Parsed_volumedetect_0 @ 0x1] max_volume: -3.5 dB\n";
+    assert_eq!(parse_max_volume(log), Some(-3.5));
+    assert_eq!(parse_max_volume("nothing"), None);
+}
+
+#[test]
+fn test_parse_gain() {
+    // Test gains given as multipliers or decibels.
+    assert_eq!("2.0".parse::<Gain>().unwrap(), Gain::Multiplier(2.0));
+    assert_eq!("+3dB".parse::<Gain>().unwrap(), Gain::Decibels(3.0));
+    assert_eq!("-6 db".parse::<Gain>().unwrap(), Gain::Decibels(-6.0));
+    assert!((Gain::Decibels(-6.0).multiplier() - 0.501).abs() < 0.001);
+    assert!((Gain::Multiplier(2.0).decibels() - 6.02).abs() < 0.01);
+    assert!("-2".parse::<Gain>().is_err());
+    assert!("loud".parse::<Gain>().is_err());
+
+    let channel: ChannelGain = "1=-3dB".parse().unwrap();
+    assert_eq!((channel.channel, channel.gain), (1, Gain::Decibels(-3.0)));
+    assert!("L=-3dB".parse::<ChannelGain>().is_err());
+}
+
+#[test]
+fn test_volume_filter() {
+    // Test the volume chain with per-channel gains and a limiter.
+    let mut args = VolumeArgs {
+        input: Path::new("samples/source.mp3").to_path_buf(),
+        output: None,
+        volume: "+3dB".parse().unwrap(),
+        channel_gain: vec!["1=0.5".parse().unwrap()],
+        limit: Some(-1.0),
+    };
+
+    assert_eq!(
+        args.filter(2, None).unwrap(),
+        "volume=3dB,pan=2c|c0=1*c0|c1=0.5*c1,alimiter=limit=0.8913:level=0"
+    );
+    assert!((args.max_gain_db() - 3.0).abs() < 1e-9);
+    assert!(args.filter(1, Some("mono")).is_err());
+
+    // The probed layout is kept, so 5.1 stays 5.1
+    args.limit = None;
+    assert_eq!(
+        args.filter(6, Some("5.1(side)")).unwrap(),
+        "volume=3dB,pan=5.1(side)|c0=1*c0|c1=0.5*c1|c2=1*c2|c3=1*c3|c4=1*c4|c5=1*c5"
+    );
+
+    args.channel_gain.clear();
+    assert_eq!(args.filter(2, Some("stereo")).unwrap(), "volume=3dB");
+}
@@ -1,4 +1,4 @@
-use omu::convert::convert_file;
+use omu::convert::{convert_file, size_report, ScreenOptions};
 use std::path::Path;
 use tempfile::tempdir;
 
@@ -39,3 +39,31 @@ fn test_convert_png_to_jpg() {
     let jpeg_magic = &[0xFF, 0xD8, 0xFF]; // First 3 bytes of a JPEG file.
     assert!(omu::utils::verify_magic_bytes(&output, jpeg_magic).unwrap());
 }
+
+#[test]
+fn test_screen_recording_args() {
+    // Test the screen-content encoder settings and the size report.
+    let mut options = ScreenOptions {
+        tune: "animation".to_string(),
+        sharp_text: true,
+        cursor_region: Some("400x300+10+20".parse().unwrap()),
+    };
+
+    let args = options.ffmpeg_args().unwrap().join(" ");
+    assert!(args.starts_with(
+        "-vf mpdecimate,addroi=x=10:y=20:w=400:h=300:qoffset=-0.4 -fps_mode vfr -c:v libx264"
+    ));
+    assert!(args.contains("-tune animation -crf 14 -pix_fmt yuv444p"));
+
+    options.tune = "film".to_string();
+    assert!(options.ffmpeg_args().is_err());
+
+    assert_eq!(
+        size_report(250_000_000, 40_000_000),
+        "Input: 250.0 MB, output: 40.0 MB (84.0% smaller)"
+    );
+    assert_eq!(
+        size_report(900, 1_200),
+        "Input: 900 B, output: 1.2 KB (33.3% larger)"
+    );
+}
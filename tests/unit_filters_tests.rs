@@ -0,0 +1,38 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use omu::filters::{apply_filters, filters_to_ffmpeg, Filter};
+
+#[test]
+fn test_parse_filter_chain() {
+    // Test parsing a chain, with the default intensity for filters without a value.
+    let chain =
+        Filter::parse_chain("grayscale, blur=2,contrast=1.2,brightness", Some(1.5)).unwrap();
+    assert_eq!(
+        chain,
+        vec![
+            Filter::Grayscale,
+            Filter::Blur(2.0),
+            Filter::Contrast(1.2),
+            Filter::Brightness(1.5)
+        ]
+    );
+
+    assert!(Filter::parse_chain("sepia", None).is_err());
+    assert!(Filter::parse_chain("blur=soft", None).is_err());
+    assert!(Filter::parse_chain("gamma=0", None).is_err());
+    assert!(Filter::parse_chain(" , ", None).is_err());
+}
+
+#[test]
+fn test_filter_chain_renders_for_images_and_video() {
+    // Test that one chain renders to an FFmpeg graph and to matching pixel ops.
+    let chain = Filter::parse_chain("brightness=0.5,saturation=0,invert", None).unwrap();
+    assert_eq!(
+        filters_to_ffmpeg(&chain),
+        "colorchannelmixer=rr=0.5:gg=0.5:bb=0.5,eq=saturation=0,negate"
+    );
+
+    let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([200, 100, 0, 255])));
+    let result = apply_filters(&img, &chain).to_rgba8();
+    // Halved to (100, 50, 0), desaturated to its luma (59), then inverted
+    assert_eq!(result.get_pixel(0, 0), &Rgba([196, 196, 196, 255]));
+}
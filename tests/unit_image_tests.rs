@@ -1,4 +1,5 @@
-use omu::image::{FilterArgs, OverlayArgs};
+use image::{DynamicImage, Rgba, RgbaImage};
+use omu::image::{collect_images, redact_image, FilterArgs, OverlayArgs, Rect, SlideshowArgs};
 use std::path::Path;
 use tempfile::tempdir;
 
@@ -53,3 +54,71 @@ fn test_apply_grayscale_filter() {
     let png_magic = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]; // Magic bytes of PNG.
     assert!(omu::utils::verify_magic_bytes(&output, png_magic).unwrap());
 }
+
+#[test]
+fn test_redact_image_region() {
+    // Test that pixelation only changes pixels inside the (clipped) region.
+    let mut img = RgbaImage::new(8, 8);
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        *pixel = Rgba([(x * 30) as u8, (y * 30) as u8, 0, 255]);
+    }
+    let img = DynamicImage::ImageRgba8(img);
+
+    let rect: Rect = "6x6+4+4".parse().unwrap();
+    let result = redact_image(&img, rect, "pixelate", 4.0)
+        .unwrap()
+        .to_rgba8();
+    let original = img.to_rgba8();
+
+    assert_eq!(result.get_pixel(3, 3), original.get_pixel(3, 3));
+    assert_eq!(result.get_pixel(4, 4), result.get_pixel(7, 7));
+    assert_ne!(result.get_pixel(7, 7), original.get_pixel(7, 7));
+
+    assert!("6x6+40+4".parse::<Rect>().is_ok());
+    assert!(redact_image(&img, "6x6+40+4".parse().unwrap(), "blur", 2.0).is_err());
+    assert!(redact_image(&img, rect, "blur", 0.0).is_err());
+    assert!(redact_image(&img, rect, "pixelate", 1.0).is_err());
+    assert!("6x6".parse::<Rect>().is_err());
+    assert!("0x6+1+1".parse::<Rect>().is_err());
+}
+
+#[test]
+fn test_slideshow_filter_graph() {
+    // Test per-image durations, crossfade offsets and music trimming.
+    let temp_dir = tempdir().unwrap();
+    for name in ["b.png", "a.jpg", "notes.txt"] {
+        std::fs::write(temp_dir.path().join(name), b"").unwrap();
+    }
+    let images = collect_images(&[temp_dir.path().to_path_buf()]).unwrap();
+    assert_eq!(
+        images,
+        vec![temp_dir.path().join("a.jpg"), temp_dir.path().join("b.png")]
+    );
+
+    let mut args = SlideshowArgs {
+        inputs: images,
+        output: None,
+        duration: vec![4.0, 3.0],
+        size: "1280x720".to_string(),
+        fps: 25,
+        fit: "fill".to_string(),
+        transition: 1.0,
+        ken_burns: false,
+        audio: Some(Path::new("music.mp3").to_path_buf()),
+        audio_fade: 2.0,
+    };
+
+    let durations = args.durations(3).unwrap();
+    assert_eq!(durations, vec![4.0, 3.0, 3.0]);
+    assert_eq!(args.total_duration(&durations), 8.0);
+
+    let graph = args.filter_graph(&durations).unwrap();
+    assert!(graph.starts_with("[0:v]scale=1280:720:force_original_aspect_ratio=increase,crop=1280:720,setsar=1,fps=25,format=yuv420p,settb=AVTB[s0];"));
+    assert!(graph.contains(
+        ";[s0][s1]xfade=transition=fade:duration=1:offset=3.000[x1];[x1][s2]xfade=transition=fade:duration=1:offset=5.000[v];"
+    ));
+    assert!(graph.ends_with(";[3:a]atrim=0:8.000,afade=t=out:st=6:d=2:curve=tri[a]"));
+
+    args.transition = 3.0;
+    assert!(args.durations(3).is_err());
+}
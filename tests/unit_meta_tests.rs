@@ -0,0 +1,106 @@
+use omu::meta::{is_cover_art, parse_chapters, write_chapters, Chapter, ChapterFormat, Tag};
+use omu::probe::MediaInfo;
+
+#[test]
+fn test_text_chapters_end_at_next_start() {
+    // Test that text chapters end where the next one starts, and the last at the duration.
+    let content = "00:00:00 Intro\n05:30 Interview\n\n01:02:03.5 Outro\n";
+    let chapters = parse_chapters(content, ChapterFormat::Text, Some(4000.0)).unwrap();
+
+    assert_eq!(
+        chapters,
+        vec![
+            Chapter {
+                start: 0.0,
+                end: 330.0,
+                title: "Intro".to_string()
+            },
+            Chapter {
+                start: 330.0,
+                end: 3723.5,
+                title: "Interview".to_string()
+            },
+            Chapter {
+                start: 3723.5,
+                end: 4000.0,
+                title: "Outro".to_string()
+            },
+        ]
+    );
+    assert!(parse_chapters("soon Intro", ChapterFormat::Text, None).is_err());
+}
+
+#[test]
+fn test_ffmetadata_chapters_round_trip() {
+    // Test that FFMETADATA output escapes titles and parses back to the same chapters.
+    let chapters = vec![
+        Chapter {
+            start: 0.0,
+            end: 12.5,
+            title: "Q&A; part=1".to_string(),
+        },
+        Chapter {
+            start: 12.5,
+            end: 30.0,
+            title: "Wrap #up".to_string(),
+        },
+    ];
+    let content = write_chapters(&chapters, ChapterFormat::Ffmetadata).unwrap();
+
+    assert!(content.starts_with(";FFMETADATA1\n"));
+    assert!(content.contains("title=Q&A\\; part\\=1\n"));
+    assert_eq!(
+        parse_chapters(&content, ChapterFormat::Ffmetadata, None).unwrap(),
+        chapters
+    );
+
+    // Other timebases are honoured
+    let other = ";FFMETADATA1\ntitle=Show\n[CHAPTER]\nTIMEBASE=1/10\nSTART=0\nEND=25\n";
+    assert_eq!(
+        parse_chapters(other, ChapterFormat::Ffmetadata, None).unwrap(),
+        vec![Chapter {
+            start: 0.0,
+            end: 2.5,
+            title: "Chapter 1".to_string()
+        }]
+    );
+
+    let json = write_chapters(&chapters, ChapterFormat::Json).unwrap();
+    assert_eq!(
+        parse_chapters(&json, ChapterFormat::Json, None).unwrap(),
+        chapters
+    );
+}
+
+#[test]
+fn test_parse_tag() {
+    // Test parsing custom key=value tags.
+    let tag: Tag = "publisher=ACME = Co".parse().unwrap();
+    assert_eq!(
+        (tag.key.as_str(), tag.value.as_str()),
+        ("publisher", "ACME = Co")
+    );
+    assert!("=value".parse::<Tag>().is_err());
+    assert!("publisher".parse::<Tag>().is_err());
+}
+
+#[test]
+fn test_is_cover_art() {
+    // Test that attached pictures and image attachments are cover art but fonts are not.
+    let info: MediaInfo = serde_json::from_str(
+        r#"{
+            "streams": [
+                {"index": 0, "codec_type": "video", "codec_name": "h264"},
+                {"index": 1, "codec_type": "video", "codec_name": "mjpeg", "disposition": {"attached_pic": 1}},
+                {"index": 2, "codec_type": "attachment", "tags": {"filename": "Roboto.ttf", "mimetype": "font/ttf"}},
+                {"index": 3, "codec_type": "attachment", "tags": {"filename": "cover.jpg", "mimetype": "image/jpeg"}},
+                {"index": 4, "codec_type": "attachment", "tags": {"filename": "Cover_Front"}}
+            ],
+            "format": {}
+        }"#,
+    )
+    .unwrap();
+
+    let covers: Vec<bool> = info.streams.iter().map(is_cover_art).collect();
+    assert_eq!(covers, vec![false, true, false, true, true]);
+}
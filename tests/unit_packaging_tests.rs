@@ -0,0 +1,56 @@
+use omu::packaging::{parse_master_playlist, parse_media_playlist, Rendition, Variant};
+
+#[test]
+fn test_parse_rendition() {
+    // Test parsing renditions with and without an audio bitrate.
+    assert_eq!(
+        "720p:2800k".parse::<Rendition>().unwrap(),
+        Rendition {
+            height: 720,
+            video_bitrate: 2800,
+            audio_bitrate: 128,
+        }
+    );
+    assert_eq!(
+        "1080:5000k:192k"
+            .parse::<Rendition>()
+            .unwrap()
+            .audio_bitrate,
+        192
+    );
+    assert!("720p".parse::<Rendition>().is_err());
+}
+
+#[test]
+fn test_parse_hls_playlists() {
+    // Test parsing master and media playlists as written by FFmpeg.
+    let master = "#EXTM3U\n#EXT-X-VERSION:6\n\
+                  #EXT-X-STREAM-INF:BANDWIDTH=5500000,RESOLUTION=1920x1080,CODECS=\"avc1.640028,mp4a.40.2\"\n\
+                  stream_0.m3u8\n\n\
+                  #EXT-X-STREAM-INF:BANDWIDTH=3080000,RESOLUTION=1280x720,CODECS=\"avc1.64001f,mp4a.40.2\"\n\
+                  stream_1.m3u8\n";
+    let variants = parse_master_playlist(master).unwrap();
+    assert_eq!(
+        variants,
+        vec![
+            Variant {
+                bandwidth: 5_500_000,
+                resolution: Some((1920, 1080)),
+                uri: "stream_0.m3u8".to_string(),
+            },
+            Variant {
+                bandwidth: 3_080_000,
+                resolution: Some((1280, 720)),
+                uri: "stream_1.m3u8".to_string(),
+            },
+        ]
+    );
+
+    let media = "#EXTM3U\n#EXT-X-VERSION:6\n#EXT-X-TARGETDURATION:6\n#EXT-X-PLAYLIST-TYPE:VOD\n\
+                 #EXTINF:6.000000,\nstream_0_00000.ts\n#EXTINF:2.500000,\nstream_0_00001.ts\n#EXT-X-ENDLIST\n";
+    let playlist = parse_media_playlist(media).unwrap();
+    assert_eq!(playlist.target_duration, 6.0);
+    assert_eq!(playlist.segments.len(), 2);
+    assert_eq!(playlist.segments[1], (2.5, "stream_0_00001.ts".to_string()));
+    assert!(playlist.ended);
+}
@@ -0,0 +1,54 @@
+use omu::subtitles::{parse_subtitles, scale_cues, shift_cues, write_subtitles, SubtitleFormat};
+
+const SRT: &str = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nworld\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\nBye\r\n";
+
+#[test]
+fn test_convert_srt_to_vtt() {
+    // Test parsing SRT and writing it back out as WebVTT.
+    let cues = parse_subtitles(SRT, SubtitleFormat::Srt).unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].start, 1000);
+    assert_eq!(cues[0].end, 2500);
+    assert_eq!(cues[0].text, "Hello\nworld");
+
+    let vtt = write_subtitles(&cues, SubtitleFormat::Vtt);
+    assert!(vtt.starts_with("WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nHello\nworld\n"));
+
+    // WebVTT output parses back to the same cues.
+    assert_eq!(parse_subtitles(&vtt, SubtitleFormat::Vtt).unwrap(), cues);
+}
+
+#[test]
+fn test_parse_ass_dialogue() {
+    // Test that ASS dialogue is parsed with override tags removed.
+    let ass = "[Script Info]\nTitle: test\n\n[Events]\n\
+               Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+               Dialogue: 0,0:00:05.10,0:00:07.00,Default,,0,0,0,,{\\i1}Hi,{\\i0} there\\Nfriend\n";
+
+    let cues = parse_subtitles(ass, SubtitleFormat::Ass).unwrap();
+    assert_eq!(cues.len(), 1);
+    assert_eq!(cues[0].start, 5100);
+    assert_eq!(cues[0].end, 7000);
+    assert_eq!(cues[0].text, "Hi, there\nfriend");
+
+    let srt = write_subtitles(&cues, SubtitleFormat::Srt);
+    assert_eq!(
+        srt,
+        "1\n00:00:05,100 --> 00:00:07,000\nHi, there\nfriend\n\n"
+    );
+}
+
+#[test]
+fn test_retime_cues() {
+    // Test shifting and rescaling cue timings.
+    let cues = parse_subtitles(SRT, SubtitleFormat::Srt).unwrap();
+
+    let shifted = shift_cues(&cues, -2.0);
+    assert_eq!(shifted.len(), 2);
+    assert_eq!((shifted[0].start, shifted[0].end), (0, 500));
+    assert_eq!((shifted[1].start, shifted[1].end), (1000, 2000));
+    assert_eq!(shift_cues(&cues, -3.0).len(), 1);
+
+    let scaled = scale_cues(&cues, 2.0);
+    assert_eq!((scaled[1].start, scaled[1].end), (1500, 2000));
+}
@@ -1,4 +1,14 @@
-use omu::video::{ExtractAudioArgs, TrimArgs};
+use image::{GrayImage, Luma};
+use omu::probe::MediaInfo;
+use omu::video::{
+    aspect_window, best_thumbnail, crop_path_expression, denoise_filter, format_scenes, fps_filter,
+    matches_template, media_summary, most_stable_crop, motion_centers, parse_cropdetect,
+    parse_qc_log, parse_quality_summary, parse_showinfo_times, parse_stats_file,
+    redact_filter_graph, scenes_from_cuts, sharpen_filter, stream_dispositions, stream_order,
+    thumbnail_filter, timelapse_filter, Anchor, ColorArgs, CropSpec, ExtractAudioArgs, Fade,
+    FrameSelection, FramesArgs, PadSpec, Redaction, ScaleSpec, StabilizeArgs, StreamValue,
+    TextArgs, Transform, TrimArgs, WatermarkArgs,
+};
 use std::path::Path;
 use tempfile::tempdir;
 
@@ -49,3 +59,493 @@ fn test_trim_video() {
     let mp4_magic = &[0x00, 0x00, 0x00, 0x20, 0x66, 0x74, 0x79, 0x70]; // Magic bytes of MP4.
     assert!(omu::utils::verify_magic_bytes(&output, mp4_magic).unwrap());
 }
+
+#[test]
+fn test_parse_crop_spec() {
+    // Test parsing pixel and aspect ratio crop specs.
+    assert_eq!(
+        "640x360+10+20".parse::<CropSpec>().unwrap(),
+        CropSpec::Pixels {
+            width: 640,
+            height: 360,
+            x: Some(10),
+            y: Some(20),
+        }
+    );
+    assert_eq!(
+        "9:16".parse::<CropSpec>().unwrap(),
+        CropSpec::Aspect { num: 9, den: 16 }
+    );
+    assert!("640x360+10".parse::<CropSpec>().is_err());
+}
+
+#[test]
+fn test_transform_filter_graph() {
+    // Test that transform operations are chained in a single filter graph.
+    let transform = Transform {
+        crop: Some("9:16".parse().unwrap()),
+        rotate: Some(90.0),
+        hflip: true,
+        vflip: false,
+        scale: Some("1080x-1".parse::<ScaleSpec>().unwrap()),
+        pad: Some(PadSpec {
+            width: 1080,
+            height: 1920,
+            fill: "black".to_string(),
+        }),
+        fade: None,
+    };
+
+    let graph = transform.filter_graph();
+    assert!(graph.starts_with("[0:v]crop="));
+    assert!(graph.contains("transpose=clock,hflip,scale=1080:-2,"));
+    assert!(graph.contains("pad=1080:1920:(ow-iw)/2:(oh-ih)/2:color=black"));
+    assert!(graph.ends_with("[v]"));
+}
+
+#[test]
+fn test_autocrop_picks_most_stable_rectangle() {
+    // Test that cropdetect output is parsed and the most frequent crop wins.
+    let log = "\
+[Parsed_cropdetect_1 @ 0x1] x1:0 x2:1279 y1:88 y2:631 w:1280 h:544 x:0 y:88 pts:0 t:0.000000 crop=1280:544:0:88
+[Parsed_cropdetect_1 @ 0x1] x1:0 x2:1279 y1:0 y2:719 w:1280 h:720 x:0 y:0 pts:1 t:1.000000 crop=1280:720:0:0
+[Parsed_cropdetect_1 @ 0x1] x1:0 x2:1279 y1:88 y2:631 w:1280 h:544 x:0 y:88 pts:2 t:2.000000 crop=1280:544:0:88
+frame=    3 fps=0.0 q=-0.0 Lsize=N/A time=00:00:03.00 bitrate=N/A speed=10x";
+
+    let crops = parse_cropdetect(log);
+    assert_eq!(crops.len(), 3);
+    assert_eq!(most_stable_crop(&crops), Some((1280, 544, 0, 88)));
+}
+
+#[test]
+fn test_reframe_aspect_window() {
+    // Test the crop window for vertical and square reframing of 1080p.
+    assert_eq!(aspect_window(1920, 1080, 9, 16), (606, 1080));
+    assert_eq!(aspect_window(1920, 1080, 1, 1), (1080, 1080));
+    assert_eq!(aspect_window(1080, 1920, 16, 9), (1080, 606));
+}
+
+#[test]
+fn test_reframe_motion_path() {
+    // Test that the crop path follows the region of change.
+    let still = GrayImage::new(100, 50);
+    let mut moved = still.clone();
+    for y in 10..20 {
+        for x in 80..90 {
+            moved.put_pixel(x, y, Luma([255]));
+        }
+    }
+
+    let centers = motion_centers(&[still, moved]);
+    assert_eq!(centers.len(), 2);
+    assert!((centers[1].0 - 0.85).abs() < 0.01);
+    assert!((centers[1].1 - 0.3).abs() < 0.01);
+
+    let expr = crop_path_expression(&[(0.0, 0.0), (1.0, 100.0)]);
+    assert_eq!(expr, "if(lt(t,1.000),(0.0)+(100.000)*(t-0.000),100.0)");
+}
+
+#[test]
+fn test_watermark_filter_graph() {
+    // Test logo scaling, opacity, anchoring and time range.
+    let args = WatermarkArgs {
+        input: Path::new("samples/source.mp4").to_path_buf(),
+        logo: Path::new("samples/source_trans.png").to_path_buf(),
+        output: None,
+        position: "bottom-right".parse().unwrap(),
+        margin: 10,
+        scale: 0.25,
+        opacity: 0.5,
+        start: Some("2".to_string()),
+        end: Some("00:00:05".to_string()),
+    };
+
+    let graph = args.filter_graph(Some(1280)).unwrap();
+    assert_eq!(
+        graph,
+        "[1:v]format=rgba,scale=320:-2,colorchannelmixer=aa=0.5[logo];\
+         [0:v][logo]overlay=W-w-10:H-h-10:enable='between(t,2,5)'[v]"
+    );
+}
+
+#[test]
+fn test_text_filter_escapes_user_text() {
+    // Test that drawtext options are built and special characters escaped.
+    let args = TextArgs {
+        input: Path::new("samples/source.mp4").to_path_buf(),
+        output: None,
+        text: Some("It's 10:30".to_string()),
+        timecode: false,
+        frame_number: false,
+        font_file: None,
+        size: 32,
+        color: "white".to_string(),
+        box_color: None,
+        position: Anchor::TopLeft,
+        margin: 5,
+        start: None,
+        end: None,
+    };
+
+    let filter = args.filter(None).unwrap();
+    assert!(filter.starts_with("drawtext=text=It\\\\\\'s 10\\\\:30:expansion=none:"));
+    assert!(filter.ends_with(":x=5:y=5"));
+}
+
+#[test]
+fn test_frames_selection() {
+    // Test that exactly one frame selection mode is accepted.
+    let mut args = FramesArgs {
+        input: Path::new("samples/source.mp4").to_path_buf(),
+        output: Path::new("frames").to_path_buf(),
+        at: Some("00:01:30".to_string()),
+        every: None,
+        keyframes: false,
+        count: None,
+        template: "frame_%04d.png".to_string(),
+    };
+    assert_eq!(args.selection().unwrap(), FrameSelection::At(90.0));
+
+    args.count = Some(5);
+    assert!(args.selection().is_err());
+}
+
+#[test]
+fn test_thumbnail_skips_black_and_blurry_frames() {
+    // Test that the sharpest non-black frame is chosen.
+    let black = GrayImage::new(32, 32);
+    let flat = GrayImage::from_pixel(32, 32, Luma([128]));
+    let mut checker = GrayImage::new(32, 32);
+    for (x, y, pixel) in checker.enumerate_pixels_mut() {
+        *pixel = Luma([if (x / 4 + y / 4) % 2 == 0 { 40 } else { 200 }]);
+    }
+
+    assert_eq!(best_thumbnail(&[black, flat, checker]), Some(2));
+    assert_eq!(best_thumbnail(&[]), None);
+}
+
+#[test]
+fn test_thumbnail_filter_caps_batch() {
+    // Test that long videos are sampled down so thumbnail buffers a bounded batch.
+    assert_eq!(
+        thumbnail_filter(Some(60.0), Some(25.0), 10),
+        "thumbnail=n=150"
+    );
+    assert_eq!(
+        thumbnail_filter(Some(7200.0), Some(30.0), 10),
+        "fps=0.4167,thumbnail=n=300"
+    );
+    assert_eq!(thumbnail_filter(None, Some(30.0), 10), "thumbnail=n=100");
+}
+
+#[test]
+fn test_matches_template() {
+    // Test that only files numbered by the frame template are matched.
+    assert!(matches_template("frame_%04d.png", "frame_0001.png"));
+    assert!(matches_template("scene_001_%d.jpg", "scene_001_12.jpg"));
+    assert!(!matches_template("frame_%04d.png", "frame_notes.png"));
+    assert!(!matches_template("frame_%04d.png", "frame_.png"));
+    assert!(!matches_template("scene_001_%d.jpg", "scene_001.mp4"));
+    assert!(matches_template("poster.png", "poster.png"));
+}
+
+#[test]
+fn test_contact_sheet_header() {
+    // Test the header summary built from probe data.
+    let info: MediaInfo = serde_json::from_str(
+        r#"{
+            "streams": [
+                {"index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080},
+                {"index": 1, "codec_type": "audio", "codec_name": "aac"}
+            ],
+            "format": {"duration": "83.5"}
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        media_summary("clip.mp4", &info),
+        vec![
+            "clip.mp4".to_string(),
+            "Duration 00:01:23  |  1920x1080  |  h264 / aac".to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_scene_detection_report() {
+    // Test that showinfo cut times become scenes and are reported as CSV.
+    let log = "\
+[Parsed_showinfo_1 @ 0x1] n:   0 pts:  61440 pts_time:4.8     duration:512 fmt:yuv420p
+[Parsed_showinfo_1 @ 0x1] n:   1 pts:  64000 pts_time:5.0     duration:512 fmt:yuv420p
+[Parsed_showinfo_1 @ 0x1] n:   2 pts: 115200 pts_time:9       duration:512 fmt:yuv420p";
+
+    let cuts = parse_showinfo_times(log);
+    assert_eq!(cuts, vec![4.8, 5.0, 9.0]);
+
+    // The cut at 5.0 is too close to 4.8 and is merged away.
+    let scenes = scenes_from_cuts(&cuts, 12.0, 1.0);
+    assert_eq!(scenes.len(), 3);
+    assert_eq!((scenes[1].start, scenes[1].end), (4.8, 9.0));
+
+    let csv = format_scenes(&scenes, "csv").unwrap();
+    assert_eq!(
+        csv,
+        "index,start,end,duration\n1,0.000,4.800,4.800\n2,4.800,9.000,4.200\n3,9.000,12.000,3.000\n"
+    );
+}
+
+#[test]
+fn test_qc_log_parsing() {
+    // Test that detector output is turned into sorted intervals.
+    let log = "\
+[blackdetect @ 0x1] black_start:0 black_end:1.5 black_duration:1.5
+[silencedetect @ 0x2] silence_start: 3.25
+[freezedetect @ 0x3] lavfi.freezedetect.freeze_start: 4
+[freezedetect @ 0x3] lavfi.freezedetect.freeze_duration: 2.5
+[freezedetect @ 0x3] lavfi.freezedetect.freeze_end: 6.5
+[silencedetect @ 0x2] silence_end: 5.75 | silence_duration: 2.5
+[silencedetect @ 0x2] silence_start: 18";
+
+    let intervals = parse_qc_log(log, 20.0);
+    let summary: Vec<(&str, f64, f64)> = intervals
+        .iter()
+        .map(|i| (i.kind.as_str(), i.start, i.end))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("black", 0.0, 1.5),
+            ("silence", 3.25, 5.75),
+            ("freeze", 4.0, 6.5),
+            ("silence", 18.0, 20.0),
+        ]
+    );
+}
+
+#[test]
+fn test_quality_stats_parsing() {
+    // Test per-frame PSNR/SSIM stats and the aggregate summary.
+    let psnr = "n:1 mse_avg:0.00 mse_y:0.00 mse_u:0.00 mse_v:0.00 psnr_avg:inf psnr_y:inf psnr_u:inf psnr_v:inf\n\
+                n:2 mse_avg:2.51 mse_y:3.10 mse_u:1.20 mse_v:1.30 psnr_avg:44.13 psnr_y:43.22 psnr_u:47.34 psnr_v:46.99\n";
+    let ssim = "n:1 Y:1.000000 U:1.000000 V:1.000000 All:1.000000 (inf)\n\
+                n:2 Y:0.981000 U:0.990000 V:0.991000 All:0.985000 (18.239)\n";
+
+    assert_eq!(
+        parse_stats_file(psnr, "psnr_avg:"),
+        vec![(1, 100.0), (2, 44.13)]
+    );
+    assert_eq!(parse_stats_file(ssim, "All:"), vec![(1, 1.0), (2, 0.985)]);
+
+    let log = "\
+[Parsed_psnr_6 @ 0x1] PSNR y:43.22 u:47.34 v:46.99 average:44.13 min:44.13 max:inf
+[Parsed_ssim_7 @ 0x2] SSIM Y:0.990500 (20.2) U:0.995 (23.0) V:0.9955 (23.4) All:0.992500 (21.2)";
+    assert_eq!(
+        parse_quality_summary(log),
+        (Some(44.13), Some(0.9925), None)
+    );
+}
+
+#[test]
+fn test_video_fade_uses_duration() {
+    // Test that the fade-out start is derived from the duration.
+    let fade = Fade {
+        fade_in: Some(1.0),
+        fade_out: Some(2.0),
+        curve: "qsin".to_string(),
+    };
+
+    assert_eq!(
+        fade.video_filter(10.0).unwrap(),
+        "fade=t=in:st=0:d=1,fade=t=out:st=8:d=2"
+    );
+    assert_eq!(
+        fade.audio_filter(10.0).unwrap(),
+        "afade=t=in:st=0:d=1:curve=qsin,afade=t=out:st=8:d=2:curve=qsin"
+    );
+}
+
+#[test]
+fn test_stream_selection_and_tags() {
+    // Test stream ordering, dropping and per-stream values.
+    let available = [0, 1, 2, 3];
+    assert_eq!(stream_order(&available, &[], &[2]).unwrap(), vec![0, 1, 3]);
+    assert_eq!(
+        stream_order(&available, &[0, 3, 1], &[]).unwrap(),
+        vec![0, 3, 1]
+    );
+    assert!(stream_order(&available, &[5], &[]).is_err());
+
+    let tag: StreamValue = "2=eng".parse().unwrap();
+    assert_eq!((tag.index, tag.value.as_str()), (2, "eng"));
+    assert!("eng".parse::<StreamValue>().is_err());
+
+    // Defaulting stream 2 clears the flag on the other audio track only,
+    // without touching their other flags
+    let streams = [(0, "video"), (1, "audio"), (2, "audio"), (3, "subtitle")];
+    assert_eq!(
+        stream_dispositions(&streams, &[2], &[3]),
+        vec![
+            (1, "-default".to_string()),
+            (2, "+default".to_string()),
+            (3, "+forced".to_string())
+        ]
+    );
+    assert_eq!(
+        stream_dispositions(&streams, &[3], &[3]),
+        vec![(3, "+default+forced".to_string())]
+    );
+}
+
+#[test]
+fn test_stabilize_filters() {
+    // Test the vidstab passes and the deshake fallback filters.
+    let mut args = StabilizeArgs {
+        input: Path::new("samples/source.mp4").to_path_buf(),
+        output: None,
+        shakiness: 8,
+        smoothing: 15,
+        zoom: None,
+        deshake: false,
+    };
+    let transforms = Path::new("C:/tmp/motion.trf");
+
+    assert_eq!(
+        args.detect_filter(transforms),
+        "vidstabdetect=shakiness=8:accuracy=15:result=C\\\\:/tmp/motion.trf"
+    );
+    assert_eq!(
+        args.transform_filter(transforms),
+        "vidstabtransform=input=C\\\\:/tmp/motion.trf:smoothing=15:optzoom=1,unsharp=5:5:0.8:3:3:0.4"
+    );
+    assert_eq!(args.deshake_filter(), "deshake=rx=48:ry=48");
+
+    args.zoom = Some(5.0);
+    assert!(args
+        .transform_filter(transforms)
+        .contains(":smoothing=15:optzoom=0:zoom=5,"));
+    assert_eq!(
+        args.deshake_filter(),
+        "deshake=rx=48:ry=48,scale=trunc(iw*1.05/2)*2:-2,crop=trunc(iw/1.05/2)*2:trunc(ih/1.05/2)*2"
+    );
+}
+
+#[test]
+fn test_color_filter_graph() {
+    // Test that colour options are chained in a fixed order.
+    let mut args = ColorArgs {
+        input: Path::new("samples/source.mp4").to_path_buf(),
+        output: None,
+        brightness: Some(1.1),
+        contrast: None,
+        saturation: Some(1.2),
+        gamma: None,
+        white_balance: Some("5600K".to_string()),
+        curves: Some("vintage".to_string()),
+        lut: Some(Path::new("C:/looks/teal,orange.cube").to_path_buf()),
+        filter: Some("blur".to_string()),
+        intensity: Some(2.0),
+    };
+
+    assert_eq!(
+        args.filter_graph().unwrap(),
+        "colortemperature=temperature=5600,colorchannelmixer=rr=1.1:gg=1.1:bb=1.1,eq=saturation=1.2,curves=preset=vintage,lut3d=file=C\\\\:/looks/teal\\,orange.cube,gblur=sigma=2"
+    );
+
+    // eq ranges are checked before FFmpeg sees them
+    args.saturation = Some(4.0);
+    assert!(args.filter_graph().is_err());
+    args.saturation = None;
+    args.gamma = Some(0.05);
+    assert!(args.filter_graph().is_err());
+    args.gamma = None;
+
+    args.curves = Some("sepia".to_string());
+    assert!(args.filter_graph().is_err());
+}
+
+#[test]
+fn test_denoise_and_sharpen_filters() {
+    // Test denoise presets and unsharp parameter validation.
+    assert_eq!(
+        denoise_filter("hqdn3d", "medium").unwrap(),
+        "hqdn3d=4:3:6:4.5"
+    );
+    assert_eq!(denoise_filter("nlmeans", "strong").unwrap(), "nlmeans=s=6");
+    assert!(denoise_filter("median", "light").is_err());
+    assert!(denoise_filter("hqdn3d", "extreme").is_err());
+
+    assert_eq!(
+        sharpen_filter(1.5, 7).unwrap(),
+        "unsharp=lx=7:ly=7:la=1.5:cx=3:cy=3:ca=0"
+    );
+    assert!(sharpen_filter(1.0, 4).is_err());
+    assert!(sharpen_filter(6.0, 5).is_err());
+}
+
+#[test]
+fn test_redact_filter_graph() {
+    // Test fixed, timed and keyframed redaction regions.
+    let timed: Redaction = "100x50+10+20@00:01-5".parse().unwrap();
+    let keyframed: Redaction = "2=40x40+0+0;0=20x20+100+100".parse().unwrap();
+    assert!(matches!(
+        timed,
+        Redaction::Fixed {
+            start: Some(1.0),
+            end: Some(5.0),
+            ..
+        }
+    ));
+    assert!("1=40x40+0+0".parse::<Redaction>().is_err());
+
+    assert_eq!(
+        redact_filter_graph(std::slice::from_ref(&timed), "pixelate", 16, (640, 360)).unwrap(),
+        "[0:v]split=2[base][r0];[r0]crop=100:50:x='10':y='20',scale=7:4:flags=area,scale=100:50:flags=neighbor[b0];[base][b0]overlay=x='10':y='20':enable='between(t,1,5)'[v]"
+    );
+
+    // Keyframes are sorted; the box keeps the largest size and follows the centre
+    let graph = redact_filter_graph(&[timed, keyframed], "blur", 30, (640, 360)).unwrap();
+    assert!(graph.starts_with("[0:v]split=3[base][r0][r1];"));
+    assert!(graph.contains("crop=100:50:x='10':y='20',boxblur=12:2[b0]"));
+    assert!(graph.contains(
+        "[r1]crop=40:40:x='clip(if(lt(t,2.000),(110.0)+(-45.000)*(t-0.000),20.0)-20,0,600)'"
+    ));
+    assert!(graph.ends_with(":enable='between(t,0,2)'[v]"));
+
+    // Regions too small to blur are covered by a single averaged block
+    let small: Redaction = "6x3+10+10".parse().unwrap();
+    assert_eq!(
+        redact_filter_graph(std::slice::from_ref(&small), "blur", 10, (640, 360)).unwrap(),
+        "[0:v]split=2[base][r0];[r0]crop=6:3:x='10':y='10',scale=1:1:flags=area,scale=6:3:flags=neighbor[b0];[base][b0]overlay=x='10':y='10'[v]"
+    );
+
+    // A strength that would leave the region visible is rejected
+    assert!(redact_filter_graph(std::slice::from_ref(&small), "blur", 0, (640, 360)).is_err());
+    assert!(redact_filter_graph(std::slice::from_ref(&small), "pixelate", 1, (640, 360)).is_err());
+
+    let outside: Redaction = "10x10+700+0".parse().unwrap();
+    assert!(redact_filter_graph(&[outside], "blur", 10, (640, 360)).is_err());
+}
+
+#[test]
+fn test_timelapse_and_fps_filters() {
+    // Test timelapse frame selection and frame rate conversion modes.
+    assert_eq!(
+        timelapse_filter(30, "drop").unwrap(),
+        "select='not(mod(n,30))',setpts=N/FRAME_RATE/TB"
+    );
+    assert_eq!(
+        timelapse_filter(4, "blend").unwrap(),
+        "tmix=frames=4,select='not(mod(n,4))',setpts=N/FRAME_RATE/TB"
+    );
+    assert!(timelapse_filter(1, "drop").is_err());
+    assert!(timelapse_filter(2000, "blend").is_err());
+
+    assert_eq!(fps_filter("30000/1001", "drop").unwrap(), "fps=30000/1001");
+    assert_eq!(
+        fps_filter("60", "blend").unwrap(),
+        "minterpolate=fps=60:mi_mode=blend"
+    );
+    assert!(fps_filter("0", "drop").is_err());
+    assert!(fps_filter("30", "warp").is_err());
+}